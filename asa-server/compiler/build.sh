@@ -6,6 +6,7 @@ set -euv -o pipefail
 channels_to_build="${CHANNELS_TO_BUILD-stable}"
 
 repository=asa-present
+build_commit="$(git rev-parse --short HEAD 2>/dev/null || echo unknown)"
 
 for channel in $channels_to_build; do
     cd "rust-base"
@@ -15,6 +16,7 @@ for channel in $channels_to_build; do
 
     docker build -t "${full_name}" \
            --build-arg channel="${channel}" \
+           --build-arg build_commit="${build_commit}" \
            .
 
     docker tag "${full_name}" "${image_name}"
@@ -0,0 +1,18 @@
+#!/bin/bash
+
+set -euv -o pipefail
+
+# Removes the Cargo cache volumes created when the server runs with
+# `ASA_CARGO_CACHE` set. The next worker starts from the image again.
+
+channels_to_clean="${CHANNELS_TO_CLEAN-stable beta nightly}"
+
+volume_prefix="${ASA_CARGO_CACHE:-asa-present-cargo-cache}"
+
+for channel in $channels_to_clean; do
+    image_name="rust-${channel}"
+
+    docker volume rm --force \
+           "${volume_prefix}-${image_name}-target" \
+           "${volume_prefix}-${image_name}-registry"
+done
@@ -0,0 +1,197 @@
+// Line-level differences between two compiler outputs, ignoring the
+// label numbers and hashes that change whenever anything else does
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+	Same,
+	Removed,
+	Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+	pub kind: DiffKind,
+	/// Zero-based line numbers; only the side the line appears on has
+	/// one.
+	pub before_line: Option<usize>,
+	pub after_line: Option<usize>,
+	/// The original text, from the after side for added lines and the
+	/// before side otherwise.
+	pub text: String,
+}
+
+// Past this many compared line pairs, the changed middle is reported as
+// removed and added wholesale instead of aligned line by line
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+pub fn diff_output(before: &str, after: &str) -> Vec<DiffLine> {
+	let before_lines: Vec<_> = before.lines().collect();
+	let after_lines: Vec<_> = after.lines().collect();
+	let before_keys: Vec<_> = before_lines.iter().map(|line| normalize_line(line)).collect();
+	let after_keys: Vec<_> = after_lines.iter().map(|line| normalize_line(line)).collect();
+
+	let prefix = before_keys.iter().zip(&after_keys).take_while(|(b, a)| b == a).count();
+	let suffix = before_keys[prefix..]
+		.iter()
+		.rev()
+		.zip(after_keys[prefix..].iter().rev())
+		.take_while(|(b, a)| b == a)
+		.count();
+
+	let before_middle = prefix..before_keys.len() - suffix;
+	let after_middle = prefix..after_keys.len() - suffix;
+
+	let mut ops = vec![DiffKind::Same; prefix];
+	ops.extend(align(&before_keys[before_middle], &after_keys[after_middle]));
+	ops.extend(vec![DiffKind::Same; suffix]);
+
+	let (mut b, mut a) = (0, 0);
+	ops.into_iter()
+		.map(|kind| {
+			let line = match kind {
+				DiffKind::Same => DiffLine {
+					kind,
+					before_line: Some(b),
+					after_line: Some(a),
+					text: before_lines[b].to_owned(),
+				},
+				DiffKind::Removed => DiffLine {
+					kind,
+					before_line: Some(b),
+					after_line: None,
+					text: before_lines[b].to_owned(),
+				},
+				DiffKind::Added => DiffLine {
+					kind,
+					before_line: None,
+					after_line: Some(a),
+					text: after_lines[a].to_owned(),
+				},
+			};
+
+			if kind != DiffKind::Added {
+				b += 1;
+			}
+			if kind != DiffKind::Removed {
+				a += 1;
+			}
+			line
+		})
+		.collect()
+}
+
+// A longest common subsequence, with removals before additions in each
+// changed run
+fn align(before: &[String], after: &[String]) -> Vec<DiffKind> {
+	let (n, m) = (before.len(), after.len());
+
+	if n * m > MAX_TABLE_SIZE {
+		let mut ops = vec![DiffKind::Removed; n];
+		ops.extend(vec![DiffKind::Added; m]);
+		return ops;
+	}
+
+	// lengths[i][j] is the LCS length of before[i..] and after[j..]
+	let width = m + 1;
+	let mut lengths = vec![0u32; (n + 1) * width];
+	for i in (0..n).rev() {
+		for j in (0..m).rev() {
+			lengths[i * width + j] = if before[i] == after[j] {
+				lengths[(i + 1) * width + j + 1] + 1
+			} else {
+				lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+			};
+		}
+	}
+
+	let mut ops = Vec::with_capacity(n + m);
+	let (mut i, mut j) = (0, 0);
+	while i < n && j < m {
+		if before[i] == after[j] {
+			ops.push(DiffKind::Same);
+			i += 1;
+			j += 1;
+		} else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+			ops.push(DiffKind::Removed);
+			i += 1;
+		} else {
+			ops.push(DiffKind::Added);
+			j += 1;
+		}
+	}
+	ops.extend(vec![DiffKind::Removed; n - i]);
+	ops.extend(vec![DiffKind::Added; m - j]);
+	ops
+}
+
+// Numbered local labels (`.LBB0_3`, `.Ltmp12`), symbol hashes, and
+// content-addressed allocations (`alloc_61247b90e1706a3f65e71312b599d3d1`)
+// are replaced by placeholders
+fn normalize_line(line: &str) -> String {
+	lazy_static! {
+		static ref LABEL_NUMBER_REGEX: Regex = Regex::new(r"(\.L[a-zA-Z_]*?)\d+(_\d+)?\b").unwrap();
+		static ref MANGLED_HASH_REGEX: Regex = Regex::new(r"17h[0-9a-f]{16}E").unwrap();
+		static ref ALLOC_REGEX: Regex = Regex::new(r"alloc_[0-9a-f]{32}").unwrap();
+	}
+
+	let line = super::pipeline::strip_hashes(line.trim_end());
+	let line = LABEL_NUMBER_REGEX.replace_all(&line, "${1}N");
+	let line = MANGLED_HASH_REGEX.replace_all(&line, "E");
+	ALLOC_REGEX.replace_all(&line, "alloc_N").into_owned()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn kinds(diff: &[DiffLine]) -> Vec<(DiffKind, &str)> {
+		diff.iter().map(|line| (line.kind, line.text.trim())).collect()
+	}
+
+	#[test]
+	fn changed_instructions_found() {
+		let before = "add:\n\tlea\teax, [rsi + rdi]\n\tret\n";
+		let after = "add:\n\tmov\teax, edi\n\tadd\teax, esi\n\tret\n";
+
+		assert_eq!(
+			kinds(&diff_output(before, after)),
+			[
+				(DiffKind::Same, "add:"),
+				(DiffKind::Removed, "lea\teax, [rsi + rdi]"),
+				(DiffKind::Added, "mov\teax, edi"),
+				(DiffKind::Added, "add\teax, esi"),
+				(DiffKind::Same, "ret"),
+			]
+		);
+	}
+
+	#[test]
+	fn renumbered_labels_and_hashes_ignored() {
+		let before = "playground::add::h785a9559085558a3:\n\tjmp\t.LBB0_2\n.LBB0_2:\n\tlea\trax, [rip + .Lalloc_61247b90e1706a3f65e71312b599d3d1]";
+		let after = "playground::add::h0a8138ea7a6fe492:\n\tjmp\t.LBB3_7\n.LBB3_7:\n\tlea\trax, [rip + .Lalloc_1e981fbc9c39c01cca7c2af5bf795138]";
+
+		let diff = diff_output(before, after);
+
+		assert!(diff.iter().all(|line| line.kind == DiffKind::Same), "{:?}", diff);
+		assert_eq!(diff[1].before_line, Some(1));
+		assert_eq!(diff[1].after_line, Some(1));
+	}
+
+	#[test]
+	fn line_numbers_follow_each_side() {
+		let diff = diff_output("a\nb\nc", "a\nc\nd");
+
+		assert_eq!(
+			diff.iter().map(|l| (l.kind, l.before_line, l.after_line)).collect::<Vec<_>>(),
+			[
+				(DiffKind::Same, Some(0), Some(0)),
+				(DiffKind::Removed, Some(1), None),
+				(DiffKind::Same, Some(2), Some(1)),
+				(DiffKind::Added, None, Some(2)),
+			]
+		);
+	}
+}
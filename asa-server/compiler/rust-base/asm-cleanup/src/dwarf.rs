@@ -0,0 +1,151 @@
+// Reading line tables, as printed by `llvm-dwarfdump --debug-line`, and
+// section layouts, as printed by `wasm-tools objdump`
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMapping {
+	pub address: u64,
+	pub line: u64,
+	pub column: u64,
+}
+
+// The rows of every line table that belong to `path`, relative to the
+// compilation directory, ordered by address
+pub fn line_mappings(debug_line: &str, path: &str) -> Vec<LineMapping> {
+	let mut mappings = Vec::new();
+	let mut directories = HashMap::new();
+	let mut files = HashMap::new();
+	let mut current_file: Option<(u64, &str)> = None;
+
+	for line in debug_line.lines() {
+		let trimmed = line.trim();
+
+		if trimmed.starts_with("debug_line[") {
+			directories.clear();
+			files.clear();
+		} else if let Some((index, value)) = indexed_entry(trimmed, "include_directories") {
+			directories.insert(index, unquote(value));
+		} else if let Some((index, _)) = indexed_entry(trimmed, "file_names") {
+			current_file = Some((index, ""));
+		} else if let Some(name) = trimmed.strip_prefix("name:") {
+			if let Some((_, file_name)) = &mut current_file {
+				*file_name = unquote(name.trim());
+			}
+		} else if let Some(dir_index) = trimmed.strip_prefix("dir_index:") {
+			let Some((index, name)) = current_file.take() else { continue };
+			let dir_index = dir_index.trim().parse().unwrap_or(0);
+			files.insert(index, join(directories.get(&dir_index).copied(), dir_index, name));
+		} else if trimmed.starts_with("0x") {
+			if let Some(mapping) = row(trimmed, &files, path) {
+				mappings.push(mapping);
+			}
+		}
+	}
+
+	mappings.sort_by_key(|m| m.address);
+	mappings.dedup_by(|b, a| (a.line, a.column) == (b.line, b.column));
+	mappings
+}
+
+// Parses `file_names[  3]:` or `include_directories[  1] = "src"`
+fn indexed_entry<'a>(line: &'a str, table: &str) -> Option<(u64, &'a str)> {
+	let line = line.strip_prefix(table)?.strip_prefix('[')?;
+	let (index, rest) = line.split_once(']')?;
+	let index = index.trim().parse().ok()?;
+	let value = rest.trim_start_matches(|c: char| c == ':' || c == '=' || c.is_whitespace());
+	Some((index, value))
+}
+
+fn unquote(value: &str) -> &str { value.trim_matches('"') }
+
+// Directory 0 is the compilation directory, so paths using it are kept
+// relative
+fn join(directory: Option<&str>, dir_index: u64, name: &str) -> String {
+	match directory {
+		Some(directory) if dir_index != 0 && !name.starts_with('/') => {
+			format!("{directory}/{name}")
+		}
+		_ => name.to_owned(),
+	}
+}
+
+// Parses `0x0000000000011871      3      8      1   0             0  is_stmt`
+fn row(line: &str, files: &HashMap<u64, String>, path: &str) -> Option<LineMapping> {
+	let mut columns = line.split_whitespace();
+	let address = u64::from_str_radix(columns.next()?.trim_start_matches("0x"), 16).ok()?;
+	let line_number = columns.next()?.parse().ok()?;
+	let column = columns.next()?.parse().ok()?;
+	let file = columns.next()?.parse().ok()?;
+
+	let is_end = columns.any(|flag| flag == "end_sequence");
+	if is_end || line_number == 0 || files.get(&file)? != path {
+		return None;
+	}
+
+	Some(LineMapping { address, line: line_number, column })
+}
+
+// Where the contents of the code section start, which is what DWARF
+// addresses in WebAssembly are relative to
+pub fn code_section_offset(objdump: &str) -> Option<u64> {
+	objdump.lines().find_map(|line| {
+		let (name, rest) = line.split_once('|')?;
+		if name.trim() != "code" {
+			return None;
+		}
+
+		let (start, _) = rest.split_once('-')?;
+		u64::from_str_radix(start.trim().trim_start_matches("0x"), 16).ok()
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn only_lines_from_the_path_kept() {
+		let debug_line = r#"
+debug_line[0x00000000]
+Line table prologue:
+         version: 4
+include_directories[  1] = "src"
+include_directories[  2] = "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860"
+file_names[  1]:
+           name: "lib.rs"
+      dir_index: 1
+       mod_time: 0x00000000
+         length: 0x00000000
+file_names[  2]:
+           name: "library/core/src/num/uint_macros.rs"
+      dir_index: 2
+       mod_time: 0x00000000
+         length: 0x00000000
+
+Address            Line   Column File   ISA Discriminator Flags
+------------------ ------ ------ ------ --- ------------- -------------
+0x0000000000011871      3      8      1   0             0  is_stmt prologue_end
+0x0000000000011876   2457     13      2   0             0  is_stmt
+0x0000000000011870      2      0      1   0             0  is_stmt
+0x0000000000011872      3      8      1   0             0
+0x0000000000011895      0     16      1   0             0
+0x000000000001189d      2      1      1   0             0  is_stmt end_sequence
+"#;
+
+		assert_eq!(
+			line_mappings(debug_line, "src/lib.rs"),
+			[
+				LineMapping { address: 0x11870, line: 2, column: 0 },
+				LineMapping { address: 0x11871, line: 3, column: 8 },
+			]
+		);
+	}
+
+	#[test]
+	fn code_section_found() {
+		let objdump = "  types                                  |        0xa -       0x55 |        75 bytes | 13 count\n  code                                   |      0x36a -    0x1b4d4 |    110954 bytes | 135 count\n";
+
+		assert_eq!(code_section_offset(objdump), Some(0x36a));
+	}
+}
@@ -0,0 +1,392 @@
+// Picking single items out of pretty-printed Rust, as printed by
+// `-Zunpretty=expanded` and `-Zunpretty=hir`
+
+// The expansion of `path` and of the items the compiler generated for it,
+// such as derived impls and `wasm_bindgen` glue. `path` is either an item
+// name (`greet`), an impl (`impl Foo` or `impl Display for Foo`), or
+// either of those inside of modules (`shapes::impl Circle`).
+pub fn extract_item(code: &str, path: &str) -> Option<String> {
+	let (modules, target) = match path.find("impl ") {
+		Some(i) => (path[..i].trim_end_matches("::"), path[i..].trim()),
+		None => path.rsplit_once("::").unwrap_or(("", path)),
+	};
+
+	let mut code = code;
+	for module in modules.split("::").filter(|m| !m.trim().is_empty()) {
+		let module = split_items(code)
+			.into_iter()
+			.find(|item| item_name(item) == ItemName::Named("mod", module.trim()))?;
+		code = module_body(module)?;
+	}
+
+	let items = split_items(code);
+	let mut keep = vec![false; items.len()];
+
+	for (i, item) in items.iter().enumerate() {
+		if item_name(item).matches(target) {
+			keep[i] = true;
+
+			let generated = items[i + 1..].iter().take_while(|item| is_generated(item));
+			for (j, _) in generated.enumerate() {
+				keep[i + 1 + j] = true;
+			}
+		}
+	}
+
+	let kept: Vec<_> =
+		items.iter().zip(keep).filter(|(_, keep)| *keep).map(|(item, _)| *item).collect();
+	if kept.is_empty() {
+		return None;
+	}
+
+	Some(dedent(&kept.join("\n")))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ItemName<'a> {
+	Named(&'a str, &'a str),
+	Impl { trait_: Option<String>, self_ty: String },
+	Other,
+}
+
+impl ItemName<'_> {
+	fn matches(&self, target: &str) -> bool {
+		match (self, target.strip_prefix("impl ")) {
+			(ItemName::Named(_, name), None) => *name == target.trim(),
+			(ItemName::Impl { trait_, self_ty }, Some(wanted)) => {
+				let wanted = normalize(wanted);
+				match wanted.split_once(" for ") {
+					Some((wanted_trait, wanted_ty)) => {
+						trait_.as_deref().is_some_and(|t| same_path(t, wanted_trait))
+							&& same_path(self_ty, wanted_ty)
+					}
+					None => same_path(self_ty, &wanted),
+				}
+			}
+			_ => false,
+		}
+	}
+}
+
+// Compares paths exactly, or by their last segments without generics
+// when `wanted` doesn't spell those out
+fn same_path(actual: &str, wanted: &str) -> bool {
+	fn base(path: &str) -> &str {
+		let path = path.split('<').next().unwrap_or(path);
+		path.rsplit("::").next().unwrap_or(path).trim()
+	}
+
+	actual == wanted || (!wanted.contains(['<', ':']) && base(actual) == wanted.trim())
+}
+
+fn item_name(item: &str) -> ItemName<'_> {
+	const QUALIFIERS: &[&str] = &["pub", "unsafe", "async", "default", "extern", "\"C\""];
+	const KINDS: &[&str] = &[
+		"fn",
+		"struct",
+		"enum",
+		"union",
+		"trait",
+		"type",
+		"mod",
+		"static",
+		"const",
+		"macro_rules!",
+	];
+
+	let mut rest = skip_attributes(item);
+	loop {
+		let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+		let word = &rest[..word_end];
+		let after = rest[word_end..].trim_start();
+
+		let is_const_fn =
+			word == "const" && (after.starts_with("fn ") || after.starts_with("unsafe "));
+
+		if QUALIFIERS.contains(&word) || word.starts_with("pub(") || is_const_fn {
+			rest = after;
+		} else if word == "impl" || word.starts_with("impl<") {
+			return impl_name(&rest[4..]);
+		} else if KINDS.contains(&word) {
+			let name_end =
+				after.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(after.len());
+			return ItemName::Named(word, &after[..name_end]);
+		} else {
+			return ItemName::Other;
+		}
+	}
+}
+
+// Parses the rest of `impl<T> Trait for Type<T> where T: Clone {`
+fn impl_name(header: &str) -> ItemName<'static> {
+	let header = header.split('{').next().unwrap_or(header);
+	let header = header.split(" where").next().unwrap_or(header).trim_start();
+
+	let header = match header.strip_prefix('<') {
+		Some(generics) => {
+			let mut depth = 1;
+			let end = generics.find(|c| {
+				match c {
+					'<' => depth += 1,
+					'>' => depth -= 1,
+					_ => {}
+				}
+				depth == 0
+			});
+			end.map_or("", |end| &generics[end + 1..])
+		}
+		None => header,
+	};
+
+	let header = normalize(header);
+	match header.split_once(" for ") {
+		Some((trait_, self_ty)) => {
+			ItemName::Impl { trait_: Some(trait_.to_owned()), self_ty: self_ty.to_owned() }
+		}
+		None => ItemName::Impl { trait_: None, self_ty: header },
+	}
+}
+
+fn normalize(text: &str) -> String { text.split_whitespace().collect::<Vec<_>>().join(" ") }
+
+// Derives, `wasm_bindgen`, and similar macros put what they generate
+// directly after the item they were applied to
+fn is_generated(item: &str) -> bool {
+	let item = item.trim_start();
+	item.starts_with("#[automatically_derived]") || item.starts_with("const _:")
+}
+
+fn skip_attributes(item: &str) -> &str {
+	let mut rest = item.trim_start();
+
+	loop {
+		if rest.starts_with("//") {
+			rest = rest.split_once('\n').map_or("", |(_, r)| r).trim_start();
+		} else if rest.starts_with("#[") || rest.starts_with("#![") {
+			let end = structure(rest).into_iter().scan(0, |depth, (i, c)| {
+				match c {
+					'[' => *depth += 1,
+					']' => *depth -= 1,
+					_ => {}
+				}
+				Some((i, *depth))
+			});
+			let end = end.skip(1).find(|&(_, depth)| depth == 0);
+			match end {
+				Some((i, _)) => rest = rest[i + 1..].trim_start(),
+				None => return "",
+			}
+		} else {
+			return rest;
+		}
+	}
+}
+
+fn module_body(module: &str) -> Option<&str> {
+	let module = skip_attributes(module);
+	let open = module.find('{')?;
+	let close = module.rfind('}')?;
+	module.get(open + 1..close)
+}
+
+// Splits code into its items. Each item starts at the beginning of its
+// first line so that its indentation is kept.
+fn split_items(code: &str) -> Vec<&str> {
+	let mut items = Vec::new();
+	let mut depth = 0;
+	let mut start = 0;
+
+	for (i, c) in structure(code) {
+		let end = match c {
+			'{' | '(' | '[' => {
+				depth += 1;
+				continue;
+			}
+			'}' | ')' | ']' => {
+				depth -= 1;
+				if depth != 0 || c != '}' {
+					continue;
+				}
+
+				let after = &code[i + 1..];
+				let trimmed = after.trim_start();
+				if trimmed.starts_with(';') {
+					i + 1 + (after.len() - trimmed.len()) + 1
+				} else {
+					i + 1
+				}
+			}
+			';' if depth == 0 => i + 1,
+			_ => continue,
+		};
+
+		if end > start {
+			push_item(&mut items, &code[start..end]);
+			start = end;
+		}
+	}
+
+	push_item(&mut items, &code[start..]);
+	items
+}
+
+fn push_item<'a>(items: &mut Vec<&'a str>, text: &'a str) {
+	let Some(first) = text.find(|c: char| !c.is_whitespace()) else { return };
+	let line_start = text[..first].rfind('\n').map_or(0, |i| i + 1);
+	items.push(text[line_start..].trim_end());
+}
+
+fn dedent(text: &str) -> String {
+	let indent = text
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| line.len() - line.trim_start().len())
+		.min()
+		.unwrap_or(0);
+
+	text.lines().map(|line| line.get(indent..).unwrap_or("")).collect::<Vec<_>>().join("\n")
+}
+
+// The brackets and semicolons of `code` with their byte offsets, skipping
+// over comments and string and character literals
+fn structure(code: &str) -> Vec<(usize, char)> {
+	let bytes = code.as_bytes();
+	let is_ident = |i: usize| bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_';
+	let mut result = Vec::new();
+	let mut i = 0;
+
+	while i < bytes.len() {
+		let next = bytes.get(i + 1).copied();
+
+		i = match bytes[i] {
+			b'/' if next == Some(b'/') => code[i..].find('\n').map_or(bytes.len(), |n| i + n),
+			b'/' if next == Some(b'*') => {
+				code[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4)
+			}
+			b'"' => string_end(bytes, i + 1),
+			b'r' if (i == 0 || !is_ident(i - 1)) && matches!(next, Some(b'"' | b'#')) => {
+				raw_string_end(code, i + 1).unwrap_or(i + 1)
+			}
+			b'\'' => char_end(code, i),
+			c @ (b'{' | b'}' | b'(' | b')' | b'[' | b']' | b';') => {
+				result.push((i, c as char));
+				i + 1
+			}
+			_ => i + 1,
+		};
+	}
+
+	result
+}
+
+fn string_end(bytes: &[u8], mut i: usize) -> usize {
+	while i < bytes.len() {
+		match bytes[i] {
+			b'\\' => i += 2,
+			b'"' => return i + 1,
+			_ => i += 1,
+		}
+	}
+	bytes.len()
+}
+
+// `start` is just after the `r` of `r#"..."#`
+fn raw_string_end(code: &str, start: usize) -> Option<usize> {
+	let hashes = code[start..].bytes().take_while(|&b| b == b'#').count();
+	let body = start + hashes;
+	if code.as_bytes().get(body) != Some(&b'"') {
+		return None;
+	}
+
+	let terminator = format!("\"{}", "#".repeat(hashes));
+	let end = code[body + 1..].find(&terminator)?;
+	Some(body + 1 + end + terminator.len())
+}
+
+// Tells character literals apart from lifetimes
+fn char_end(code: &str, start: usize) -> usize {
+	let rest = &code[start + 1..];
+
+	if rest.starts_with('\\') {
+		return rest[2..].find('\'').map_or(code.len(), |n| start + 1 + 2 + n + 1);
+	}
+
+	let char_len = rest.chars().next().map_or(0, char::len_utf8);
+	if rest[char_len..].starts_with('\'') {
+		start + 1 + char_len + 1
+	} else {
+		start + 1
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const EXPANDED: &str = r#"#![feature(prelude_import)]
+#[prelude_import]
+use std::prelude::rust_2021::*;
+#[macro_use]
+extern crate std;
+use wasm_bindgen::prelude::*;
+struct Dummy;
+#[automatically_derived]
+impl ::core::fmt::Debug for Dummy {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        ::core::fmt::Formatter::write_str(f, "Dummy")
+    }
+}
+pub fn greet(name: &str) -> String {
+    ::alloc::__export::must_use({
+            let res = ::alloc::fmt::format(format_args!("Hello, {0}! }}", name));
+            res
+        })
+}
+#[automatically_derived]
+const _: () =
+    {
+        #[export_name = "greet"]
+        pub unsafe extern "C" fn __wasm_bindgen_generated_greet() {}
+    };
+const GREETING: &str = "{";
+mod shapes {
+    pub struct Circle;
+    impl Circle {
+        pub fn area(&self) -> f64 { 0.0 }
+    }
+    impl<'a> From<&'a str> for Circle {
+        fn from(_: &'a str) -> Self { Circle }
+    }
+}
+"#;
+
+	#[test]
+	fn function_kept_with_generated_items() {
+		let greet = extract_item(EXPANDED, "greet").unwrap();
+
+		assert!(greet.starts_with("pub fn greet"), "{}", greet);
+		assert!(greet.contains("__wasm_bindgen_generated_greet"), "{}", greet);
+		assert!(!greet.contains("GREETING"), "{}", greet);
+		assert!(!greet.contains("Dummy"), "{}", greet);
+	}
+
+	#[test]
+	fn impls_matched_by_type_and_trait() {
+		let debug = extract_item(EXPANDED, "impl Debug for Dummy").unwrap();
+		assert!(debug.starts_with("#[automatically_derived]"), "{}", debug);
+		assert!(debug.ends_with('}'), "{}", debug);
+
+		let circle = extract_item(EXPANDED, "shapes::impl Circle").unwrap();
+		assert!(circle.starts_with("impl Circle {\n    pub fn area"), "{}", circle);
+		assert!(circle.contains("impl<'a> From<&'a str> for Circle"), "{}", circle);
+		assert!(!circle.contains("pub struct Circle"), "{}", circle);
+	}
+
+	#[test]
+	fn missing_items_found_nowhere() {
+		assert_eq!(extract_item(EXPANDED, "goodbye"), None);
+		assert_eq!(extract_item(EXPANDED, "circles::Circle"), None);
+		assert_eq!(extract_item(EXPANDED, "shapes::Circle").as_deref(), Some("pub struct Circle;"));
+	}
+}
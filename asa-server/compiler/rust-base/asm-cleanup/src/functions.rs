@@ -0,0 +1,341 @@
+// Picking single functions out of assembly, LLVM IR, and MIR
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use rustc_demangle::demangle;
+
+// Matches `lib::add` and `<lib::Foo as core::fmt::Debug>::fmt` when
+// asked for `add` or `fmt`, as well as their full names
+pub(crate) fn same_name(full: &str, name: &str) -> bool {
+	full == name || full.strip_suffix(name).is_some_and(|prefix| prefix.ends_with("::"))
+}
+
+// The function called `name` from demangled assembly, filtered like
+// `filter_asm`, along with the data it uses
+pub fn extract_asm_function(block: &str, name: &str) -> Option<String> {
+	let lines: Vec<_> = block.lines().collect();
+
+	let mut kept = String::new();
+	for index in asm_function_lines(&lines, name)? {
+		kept.push_str(lines[index]);
+		kept.push('\n');
+	}
+
+	Some(super::filter_asm(&kept))
+}
+
+// The indices of the lines that aren't part of any other function. Data
+// that's only used by the other functions is then unused, so
+// `filter_asm` removes it.
+pub(crate) fn asm_function_lines(lines: &[&str], name: &str) -> Option<Vec<usize>> {
+	let functions = asm_functions(lines);
+
+	if !functions.iter().any(|(function, _)| same_name(function, name)) {
+		return None;
+	}
+
+	let other_functions: Vec<_> = functions
+		.iter()
+		.filter(|(function, _)| !same_name(function, name))
+		.map(|(_, range)| range)
+		.collect();
+
+	let kept = (0..lines.len())
+		.filter(|index| !other_functions.iter().any(|range| range.contains(index)))
+		.collect();
+	Some(kept)
+}
+
+// Each function runs from its label to its `.size` directive
+fn asm_functions<'a>(lines: &[&'a str]) -> Vec<(&'a str, Range<usize>)> {
+	lazy_static! {
+		// Example:    .type main,@function
+		static ref FUNCTION_REGEX: Regex = Regex::new(r"^\s+\.type\s*(.*),[@%]function$").unwrap();
+	}
+
+	let names =
+		lines.iter().filter_map(|line| FUNCTION_REGEX.captures(line).and_then(|cap| cap.get(1)));
+
+	names
+		.filter_map(|name| {
+			let name = name.as_str();
+			let label = format!("{name}:");
+			let start = lines.iter().position(|line| line.trim_end() == label)?;
+
+			let is_size = |line: &&str| {
+				let size = line.trim_start().strip_prefix(".size");
+				size.and_then(|size| size.trim_start().strip_prefix(name))
+					.is_some_and(|rest| rest.starts_with(','))
+			};
+			let end =
+				lines[start..].iter().position(is_size).map_or(lines.len(), |n| start + n + 1);
+
+			Some((name, start..end))
+		})
+		.collect()
+}
+
+// The `define` of the function called `name`, along with the globals
+// and declarations it refers to
+pub fn extract_llvm_ir_function(ir: &str, name: &str) -> Option<String> {
+	lazy_static! {
+		// Example:    call void @llvm.lifetime.start.p0(ptr nonnull %x)
+		static ref GLOBAL_REF_REGEX: Regex = Regex::new(r#"@([-a-zA-Z$._0-9]+|"[^"]*")"#).unwrap();
+	}
+
+	// The first global on an item's first line is the one it defines
+	fn global_name<'a>(item: &[&'a str]) -> Option<&'a str> {
+		let line = item.iter().find(|line| !line.starts_with(';'))?;
+		GLOBAL_REF_REGEX.captures(line).and_then(|cap| cap.get(1)).map(|m| m.as_str())
+	}
+
+	let items = top_level_items(ir, ";");
+	let is_define = |item: &[&str]| item.iter().any(|line| line.starts_with("define "));
+
+	let mut keep: Vec<_> = items
+		.iter()
+		.map(|item| {
+			let symbol = global_name(item).filter(|_| is_define(item));
+			symbol.is_some_and(|symbol| same_name(&format!("{:#}", demangle(symbol)), name))
+		})
+		.collect();
+
+	if !keep.contains(&true) {
+		return None;
+	}
+
+	// Follow references through globals, which can refer to each other
+	let mut referenced = HashSet::new();
+	loop {
+		let before = referenced.len();
+		for (item, _) in items.iter().zip(&keep).filter(|(_, keep)| **keep) {
+			for line in item.iter() {
+				let globals = GLOBAL_REF_REGEX.captures_iter(line).filter_map(|cap| cap.get(1));
+				referenced.extend(globals.map(|m| m.as_str()));
+			}
+		}
+
+		for (item, keep) in items.iter().zip(&mut keep) {
+			if !is_define(item) && global_name(item).is_some_and(|g| referenced.contains(g)) {
+				*keep = true;
+			}
+		}
+
+		if referenced.len() == before {
+			break;
+		}
+	}
+
+	Some(kept_items(&items, &keep))
+}
+
+// The body of the function called `name`, along with its promoted
+// constants and the allocations printed after it
+pub fn extract_mir_function(mir: &str, name: &str) -> Option<String> {
+	// The comments are about the whole file
+	let mut items = top_level_items(mir, "//");
+	for item in &mut items {
+		item.retain(|line| !line.starts_with("//"));
+	}
+
+	let mut keep = vec![false; items.len()];
+	let mut in_function = false;
+
+	for (item, keep) in items.iter().zip(&mut keep) {
+		let Some(header) = item.first() else { continue };
+
+		if let Some(function) = header.strip_prefix("fn ") {
+			let function = function.split('(').next().unwrap_or(function);
+			in_function = same_name(function, name);
+			*keep = in_function;
+		} else if let Some(constant) = header.strip_prefix("const ") {
+			let promoted_from =
+				constant.split("::promoted[").next().filter(|_| constant.contains("::promoted["));
+			*keep = promoted_from.is_some_and(|function| same_name(function, name));
+		} else if header.starts_with("alloc") {
+			*keep = in_function;
+		}
+	}
+
+	if !keep.contains(&true) {
+		return None;
+	}
+
+	Some(kept_items(&items, &keep))
+}
+
+// Splits printed IR or MIR into items that start in the first column,
+// such as functions with their bodies or single-line globals. Comments
+// just before an item are part of it.
+fn top_level_items<'a>(text: &'a str, comment: &str) -> Vec<Vec<&'a str>> {
+	let mut items = Vec::new();
+	let mut current = Vec::new();
+	let mut in_body = false;
+
+	for line in text.lines() {
+		if in_body {
+			current.push(line);
+			if line == "}" {
+				items.push(std::mem::take(&mut current));
+				in_body = false;
+			}
+		} else if line.trim().is_empty() {
+			current.clear();
+		} else if line.starts_with(comment) {
+			current.push(line);
+		} else {
+			current.push(line);
+			if line.trim_end().ends_with('{') {
+				in_body = true;
+			} else {
+				items.push(std::mem::take(&mut current));
+			}
+		}
+	}
+
+	items
+}
+
+fn kept_items(items: &[Vec<&str>], keep: &[bool]) -> String {
+	let kept: Vec<_> = items
+		.iter()
+		.zip(keep)
+		.filter(|(_, keep)| **keep)
+		.map(|(item, _)| item.join("\n"))
+		.collect();
+	kept.join("\n\n")
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn asm_function_kept_with_its_data() {
+		let asm = r#"	.section	.text.lib::add,"ax",@progbits
+	.type	lib::add,@function
+lib::add:
+	.cfi_startproc
+	lea	eax, [rsi + rdi]
+	ret
+.Lfunc_end0:
+	.size	lib::add, .Lfunc_end0-lib::add
+	.cfi_endproc
+	.type	lib::hello,@function
+lib::hello:
+	lea	rax, [rip + .Lalloc_1]
+	ret
+.Lfunc_end1:
+	.size	lib::hello, .Lfunc_end1-lib::hello
+	.type	<lib::Foo as core::fmt::Debug>::fmt,@function
+<lib::Foo as core::fmt::Debug>::fmt:
+	lea	rax, [rip + .Lalloc_2]
+	ret
+.Lfunc_end2:
+	.size	<lib::Foo as core::fmt::Debug>::fmt, .Lfunc_end2-<lib::Foo as core::fmt::Debug>::fmt
+.Lalloc_1:
+	.ascii	"hi"
+.Lalloc_2:
+	.ascii	"Foo"
+"#;
+
+		assert_eq!(
+			extract_asm_function(asm, "hello").as_deref(),
+			Some("\nlib::hello:\n\tlea\trax, [rip + .Lalloc_1]\n\tret\n\n.Lalloc_1:\n\t.ascii\t\"hi\"\n")
+		);
+		assert_eq!(
+			extract_asm_function(asm, "fmt").as_deref(),
+			Some("\n<lib::Foo as core::fmt::Debug>::fmt:\n\tlea\trax, [rip + .Lalloc_2]\n\tret\n\n.Lalloc_2:\n\t.ascii\t\"Foo\"\n")
+		);
+		assert_eq!(extract_asm_function(asm, "dd"), None);
+	}
+
+	#[test]
+	fn llvm_ir_function_kept_with_its_globals() {
+		let ir = r#"@alloc_1 = private unnamed_addr constant [11 x i8] c"src/lib.rs\00", align 1
+@alloc_2 = private unnamed_addr constant <{ ptr, [16 x i8] }> <{ ptr @alloc_1, [16 x i8] c"\0A" }>, align 8
+@alloc_3 = private unnamed_addr constant [2 x i8] c"hi", align 1
+
+; lib::add
+; Function Attrs: noinline
+define noundef i8 @_ZN3lib3add17h785a9559085558a3E(i8 noundef %a, i8 noundef %b) unnamed_addr #0 {
+start:
+  %_0 = add i8 %b, %a
+  ret i8 %_0
+}
+
+; lib::hello
+define { ptr, i64 } @_ZN3lib5hello17h0a8138ea7a6fe492E() unnamed_addr #1 {
+start:
+  call void @_ZN4core9panicking18panic_bounds_check17h2cd6c4f5a8e9a1b2E(i64 3, ptr @alloc_2)
+
+bb1:
+  ret { ptr, i64 } { ptr @alloc_3, i64 2 }
+}
+
+declare void @_ZN4core9panicking18panic_bounds_check17h2cd6c4f5a8e9a1b2E(i64 noundef, ptr noundef) unnamed_addr #1
+
+declare void @llvm.lifetime.end.p0(ptr captures(none)) #2
+
+attributes #0 = { noinline }
+"#;
+
+		let hello = extract_llvm_ir_function(ir, "hello").unwrap();
+		assert!(hello.starts_with("@alloc_1 ="), "{}", hello);
+		assert!(hello.contains("@alloc_3 ="), "{}", hello);
+		assert!(hello.contains("\n\nbb1:\n"), "{}", hello);
+		assert!(hello.ends_with("declare void @_ZN4core9panicking18panic_bounds_check17h2cd6c4f5a8e9a1b2E(i64 noundef, ptr noundef) unnamed_addr #1"), "{}", hello);
+		assert!(!hello.contains("lib::add"), "{}", hello);
+
+		let add = extract_llvm_ir_function(ir, "lib::add").unwrap();
+		assert!(add.starts_with("; lib::add\n; Function Attrs: noinline\ndefine"), "{}", add);
+		assert!(add.ends_with("ret i8 %_0\n}"), "{}", add);
+	}
+
+	#[test]
+	fn mir_function_kept_with_its_allocations() {
+		let mir = r#"// WARNING: This output format is intended for human consumers only
+fn <impl at src/lib.rs:2:1: 2:9>::get(_1: &Foo) -> u32 {
+    bb0: {
+        return;
+    }
+}
+
+fn hello() -> &str {
+    bb0: {
+        _0 = const "hi";
+        return;
+    }
+}
+
+alloc1 (size: 2, align: 1) {
+    68 69                                           │ hi
+}
+
+const hello::promoted[0]: &[i32; 3] = {
+    bb0: {
+        return;
+    }
+}
+
+fn dbg(_1: &Foo) -> () {
+    bb0: {
+        return;
+    }
+}
+"#;
+
+		let hello = extract_mir_function(mir, "hello").unwrap();
+		assert!(hello.starts_with("fn hello() -> &str {"), "{}", hello);
+		assert!(hello.contains("\n\nalloc1 (size: 2, align: 1) {"), "{}", hello);
+		assert!(hello.contains("\n\nconst hello::promoted[0]"), "{}", hello);
+		assert!(!hello.contains("fn dbg"), "{}", hello);
+
+		let get = extract_mir_function(mir, "get").unwrap();
+		assert!(get.starts_with("fn <impl at src/lib.rs:2:1: 2:9>::get"), "{}", get);
+		assert!(!get.contains("alloc1"), "{}", get);
+	}
+}
@@ -8,14 +8,42 @@ use petgraph::prelude::*;
 use regex::{Captures, Regex};
 use rustc_demangle::demangle;
 
-pub fn demangle_asm(block: &str) -> String {
+pub use self::diff::{diff_output, DiffKind, DiffLine};
+pub use self::dwarf::{code_section_offset, line_mappings, LineMapping};
+pub use self::expanded::extract_item;
+pub use self::functions::{extract_asm_function, extract_llvm_ir_function, extract_mir_function};
+pub use self::pipeline::{process_annotated_asm, process_asm, AsmPipeline};
+pub use self::source_lines::{annotate_asm, annotate_asm_function, filter_annotated_asm, AsmLine};
+pub use self::stats::{asm_function_stats, asm_stats, AsmFunctionStats};
+pub use self::wat::{
+	filter_wat, function_sizes, summarize_wat, WasmDataSegment, WasmExport, WasmFunction,
+	WasmFunctionSize, WasmImport, WasmSummary,
+};
+
+mod diff;
+mod dwarf;
+mod expanded;
+mod functions;
+mod pipeline;
+mod source_lines;
+mod stats;
+mod wat;
+
+pub fn demangle_asm(block: &str) -> String { demangle_symbols(block, false) }
+
+fn demangle_symbols(block: &str, keep_hashes: bool) -> String {
 	lazy_static! {
 		static ref DEMANGLE_REGEX: Regex = Regex::new(r"_[a-zA-Z0-9._$]*").unwrap();
 	}
 
 	DEMANGLE_REGEX
 		.replace_all(block, |caps: &Captures<'_>| {
-			format!("{:#}", demangle(caps.get(0).map_or("", |m| m.as_str())))
+			let symbol = demangle(caps.get(0).map_or("", |m| m.as_str()));
+			if keep_hashes {
+				format!("{}", symbol)
+			} else {
+				format!("{:#}", symbol)
+			}
 		})
 		.to_string()
 }
@@ -32,6 +60,22 @@ enum LineType<'a> {
 
 // Removes unused labels and directives from assembly
 pub fn filter_asm(block: &str) -> String {
+	let lines: Vec<_> = block.lines().collect();
+	let mut filtered_asm = String::new();
+
+	for line in filter_asm_lines(block) {
+		if let Some(index) = line {
+			filtered_asm.push_str(lines[index]);
+		}
+		filtered_asm.push('\n');
+	}
+
+	filtered_asm
+}
+
+// The indices of the lines of `block` that `filter_asm` keeps, with
+// `None` for the blank lines it adds before labels
+fn filter_asm_lines(block: &str) -> Vec<Option<usize>> {
 	use self::LineType::*;
 
 	lazy_static! {
@@ -54,13 +98,21 @@ pub fn filter_asm(block: &str) -> String {
 		// Example:    .string "Hello, world!"
 		// Note: this is a type of directive
 		// Include in results if it is part of a used label, may contain label references
-		static ref DATA_REGEX: Regex = Regex::new(r"^\s+\.(string|asciz|ascii|[1248]?byte|short|word|long|quad|value|zero)").unwrap();
+		// AArch64 adds .hword and .xword, WebAssembly adds .int8 through .int64
+		static ref DATA_REGEX: Regex = Regex::new(r"^\s+\.(string|asciz|ascii|[1248]?byte|short|[hx]?word|long|quad|value|zero|int(8|16|32|64))").unwrap();
 	}
 	lazy_static! {
 		// Example:    .type main,@function
 		// Note: this is a type of directive
 		// Never include in results, but is used to find and include functions
-		static ref FUNCTION_REGEX: Regex = Regex::new(r"^\s+\.type\s*(.*),@function$").unwrap();
+		// ARM spells it %function
+		static ref FUNCTION_REGEX: Regex = Regex::new(r"^\s+\.type\s*(.*),[@%]function$").unwrap();
+	}
+	lazy_static! {
+		// Example:    .section .rodata.GREETING,"a",@progbits
+		// Note: this is a type of directive
+		// Never include in results, but data after it doesn't belong to the previous label
+		static ref SECTION_REGEX: Regex = Regex::new(r"^\s+\.(section|text|data|bss)\b").unwrap();
 	}
 	lazy_static! {
 		// Example:    .p2align 4, 0x90
@@ -109,6 +161,9 @@ pub fn filter_asm(block: &str) -> String {
 		{
 			line_info.push(FunctionDecl);
 			opcode_operands.insert(function_cap.as_str());
+		} else if SECTION_REGEX.is_match(line) {
+			line_info.push(Directive);
+			current_label = "";
 		// DIRECTIVE_REGEX must be checked after FUNCTION_REGEX and DATA_REGEX,
 		// matches them too
 		} else if DIRECTIVE_REGEX.is_match(line) {
@@ -137,27 +192,20 @@ pub fn filter_asm(block: &str) -> String {
 
 	used_labels.extend(&data_labels);
 
-	let mut filtered_asm = String::new();
-	for (line, line_type) in block.lines().zip(&line_info) {
+	let mut filtered_lines = Vec::new();
+	for (index, line_type) in line_info.iter().enumerate() {
 		match *line_type {
-			Opcode | Misc => {
-				filtered_asm.push_str(line);
-				filtered_asm.push('\n');
-			}
-			Data(data) if used_labels.contains(&data) => {
-				filtered_asm.push_str(line);
-				filtered_asm.push('\n');
-			}
+			Opcode | Misc => filtered_lines.push(Some(index)),
+			Data(data) if used_labels.contains(&data) => filtered_lines.push(Some(index)),
 			LabelDecl(label) if used_labels.contains(&label) => {
-				filtered_asm.push('\n');
-				filtered_asm.push_str(line);
-				filtered_asm.push('\n');
+				filtered_lines.push(None);
+				filtered_lines.push(Some(index));
 			}
 			_ => (),
 		}
 	}
 
-	filtered_asm
+	filtered_lines
 }
 
 #[cfg(test)]
@@ -280,6 +328,24 @@ mod test {
 		);
 	}
 	#[test]
+	fn data_in_other_sections_removed() {
+		assert_eq!(
+			super::filter_asm(
+				"GREETING:\n\t.asciz\t\"hi\"\n\t.section\t.custom_section.producers,\"\",@\n\t.int8\t1\n\t.ascii\t\"rustc\"\ngreeting:\n\ti32.const\tGREETING\n"
+			),
+			"\nGREETING:\n\t.asciz\t\"hi\"\n\ti32.const\tGREETING\n"
+		);
+	}
+	#[test]
+	fn aarch64_data_kept() {
+		assert_eq!(
+			super::filter_asm(
+				"\t.type\tread,%function\nread:\n\tadrp\tx0, :got:VALUES\n\tret\nVALUES:\n\t.xword\t1\n\t.hword\t2\n"
+			),
+			"\nread:\n\tadrp\tx0, :got:VALUES\n\tret\n\nVALUES:\n\t.xword\t1\n\t.hword\t2\n"
+		);
+	}
+	#[test]
 	fn comment_retained() {
 		assert_eq!(super::filter_asm("# %bb.0:\n subq $24, %rsp\n"), "# %bb.0:\n subq $24, %rsp\n")
 	}
@@ -0,0 +1,267 @@
+// Combining the cleanup steps, which all work on the text of the assembly
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::AsmLine;
+
+/// The cleanup steps to run. They always run in the order of the
+/// fields, so that filtering sees the mangled names it understands.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AsmPipeline {
+	/// Remove unused labels and directives, like `filter_asm`.
+	pub filter: bool,
+	/// Demangle symbols, keeping their hashes.
+	pub demangle: bool,
+	/// Remove the hashes from demangled symbols.
+	pub strip_hashes: bool,
+	/// Remove comment lines and trailing comments.
+	pub strip_comments: bool,
+	/// Shrink runs of spaces and tabs after the indentation, and runs of
+	/// blank lines, to one.
+	pub collapse_whitespace: bool,
+}
+
+pub fn process_asm(block: &str, pipeline: AsmPipeline) -> String {
+	let mut block = block.to_owned();
+
+	if pipeline.filter {
+		block = super::filter_asm(&block);
+	}
+	if pipeline.demangle {
+		block = super::demangle_symbols(&block, true);
+	}
+	if pipeline.strip_hashes {
+		block = strip_hashes(&block);
+	}
+	if pipeline.strip_comments {
+		block = strip_comments(&block);
+	}
+	if pipeline.collapse_whitespace {
+		block = collapse_whitespace(&block);
+	}
+
+	block
+}
+
+// The same steps for lines from `annotate_asm`, so that they stay the
+// same as the lines of the code. Filtering needs the directives, so it
+// has to happen while annotating and is ignored here.
+pub fn process_annotated_asm(lines: Vec<AsmLine>, pipeline: AsmPipeline) -> Vec<AsmLine> {
+	let mut result: Vec<AsmLine> = Vec::new();
+
+	for mut line in lines {
+		if pipeline.demangle {
+			line.text = super::demangle_symbols(&line.text, true);
+		}
+		if pipeline.strip_hashes {
+			line.text = strip_hashes(&line.text);
+		}
+		if pipeline.strip_comments {
+			match strip_comment(&line.text) {
+				Some(text) => line.text = text.to_owned(),
+				None => continue,
+			}
+		}
+		if pipeline.collapse_whitespace {
+			let previous_blank = result.last().is_some_and(|l| l.text.trim().is_empty());
+			if line.text.trim().is_empty() && previous_blank {
+				continue;
+			}
+			line.text = collapse_indented_line(&line.text);
+		}
+
+		result.push(line);
+	}
+
+	result
+}
+
+// Removes legacy hashes (`lib::add::h785a9559085558a3`) and crate
+// disambiguators (`core[b5e5f5d6c2a1a2f3]::fmt`)
+pub(crate) fn strip_hashes(block: &str) -> String {
+	lazy_static! {
+		static ref HASH_REGEX: Regex =
+			Regex::new(r"::h[0-9a-f]{16}\b|([a-zA-Z0-9_])\[[0-9a-f]{1,16}\]").unwrap();
+	}
+
+	HASH_REGEX.replace_all(block, "$1").to_string()
+}
+
+// Comments start with `#` on x86 and `//` on AArch64. AArch64 also uses
+// `#` for immediates, but never with a space after it.
+fn strip_comments(block: &str) -> String {
+	let mut result = String::new();
+
+	for line in block.lines().filter_map(strip_comment) {
+		result.push_str(line);
+		result.push('\n');
+	}
+
+	result
+}
+
+// `None` for lines that are only a comment
+fn strip_comment(line: &str) -> Option<&str> {
+	let trimmed = line.trim_start();
+	if trimmed.starts_with('#') || trimmed.starts_with("//") {
+		return None;
+	}
+
+	Some(trailing_comment(line).map_or(line, |start| line[..start].trim_end()))
+}
+
+fn trailing_comment(line: &str) -> Option<usize> {
+	let mut in_string = false;
+	let mut previous = ' ';
+
+	for (i, c) in line.char_indices() {
+		let rest = &line[i..];
+
+		match c {
+			'"' if previous != '\\' => in_string = !in_string,
+			_ if in_string => {}
+			_ if previous.is_whitespace() && (rest.starts_with("# ") || rest.starts_with("//")) => {
+				return Some(i);
+			}
+			_ => {}
+		}
+
+		previous = c;
+	}
+
+	None
+}
+
+fn collapse_whitespace(block: &str) -> String {
+	let mut result = String::new();
+	let mut previous_blank = false;
+
+	for line in block.lines() {
+		let is_blank = line.trim().is_empty();
+		if is_blank && previous_blank {
+			continue;
+		}
+		previous_blank = is_blank;
+
+		result.push_str(&collapse_indented_line(line));
+		result.push('\n');
+	}
+
+	result
+}
+
+fn collapse_indented_line(line: &str) -> String {
+	let indent = &line[..line.len() - line.trim_start().len()];
+	format!("{}{}", indent, collapse_line(line.trim()))
+}
+
+// Leaves the insides of strings alone
+fn collapse_line(line: &str) -> String {
+	let mut result = String::new();
+	let mut in_string = false;
+	let mut previous = ' ';
+
+	for c in line.chars() {
+		if c == '"' && previous != '\\' {
+			in_string = !in_string;
+		}
+
+		let is_space = c == ' ' || c == '\t';
+		if in_string || !is_space {
+			result.push(c);
+		} else if !(previous == ' ' || previous == '\t') {
+			result.push(' ');
+		}
+
+		previous = c;
+	}
+
+	result
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const ASM: &str = "\t.type\t_ZN10playground3add17h785a9559085558a3E,@function\n_ZN10playground3add17h785a9559085558a3E:\n# %bb.0:\n\tlea\teax, [rsi + rdi]    # kill: def $eax\n\tret\n.Lfunc_end0:\n";
+
+	#[test]
+	fn nothing_changed_by_default() {
+		assert_eq!(process_asm(ASM, AsmPipeline::default()), ASM);
+	}
+
+	#[test]
+	fn filtered_and_demangled() {
+		let pipeline = AsmPipeline { filter: true, demangle: true, ..Default::default() };
+
+		assert_eq!(
+			process_asm(ASM, pipeline),
+			"\nplayground::add::h785a9559085558a3:\n# %bb.0:\n\tlea\teax, [rsi + rdi]    # kill: def $eax\n\tret\n"
+		);
+	}
+
+	#[test]
+	fn hashes_stripped() {
+		assert_eq!(
+			strip_hashes("call playground::add::h785a9559085558a3\ncall core[b5e5f5d6c2a1a2f3]::fmt::write\nmov eax, dword ptr [rdi]"),
+			"call playground::add\ncall core::fmt::write\nmov eax, dword ptr [rdi]"
+		);
+	}
+
+	#[test]
+	fn comments_stripped() {
+		assert_eq!(
+			strip_comments("# %bb.0:\n\tlea\teax, [rsi + rdi]  # kill\n\tmov\tw0, #1 // =0x1\n\t.ascii\t\"a # b\"\n"),
+			"\tlea\teax, [rsi + rdi]\n\tmov\tw0, #1\n\t.ascii\t\"a # b\"\n"
+		);
+	}
+
+	#[test]
+	fn whitespace_collapsed() {
+		assert_eq!(
+			collapse_whitespace("\tlea\teax,   [rsi + rdi]  \n\n\n\t.ascii\t\"a   b\"\n"),
+			"\tlea eax, [rsi + rdi]\n\n\t.ascii \"a   b\"\n"
+		);
+	}
+
+	#[test]
+	fn annotated_lines_match_the_code() {
+		let pipeline = AsmPipeline {
+			demangle: true,
+			strip_hashes: true,
+			strip_comments: true,
+			collapse_whitespace: true,
+			..Default::default()
+		};
+		let lines = ASM
+			.lines()
+			.chain(["", "", "\tret"])
+			.enumerate()
+			.map(|(i, text)| AsmLine { text: text.to_owned(), source_line: Some(i as u64) })
+			.collect();
+
+		let lines = process_annotated_asm(lines, pipeline);
+
+		let code = process_asm(&format!("{}\n\n\tret\n", ASM), pipeline);
+		let texts: Vec<_> = lines.iter().map(|l| &*l.text).collect();
+		assert_eq!(texts, code.lines().collect::<Vec<_>>());
+		assert_eq!(lines[2].source_line, Some(3));
+	}
+
+	#[test]
+	fn every_step_in_order() {
+		let pipeline = AsmPipeline {
+			filter: true,
+			demangle: true,
+			strip_hashes: true,
+			strip_comments: true,
+			collapse_whitespace: true,
+		};
+
+		assert_eq!(
+			process_asm(ASM, pipeline),
+			"\nplayground::add:\n\tlea eax, [rsi + rdi]\n\tret\n"
+		);
+	}
+}
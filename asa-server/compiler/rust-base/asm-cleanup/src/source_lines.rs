@@ -0,0 +1,234 @@
+// Linking assembly back to the source lines it came from, using the
+// `.file` and `.loc` directives that are emitted along with debug info
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmLine {
+	pub text: String,
+	/// The line of the source file that this instruction came from, if
+	/// it came from that file at all. Only instructions have one.
+	pub source_line: Option<u64>,
+}
+
+// Every line of the assembly, with instructions tagged by their line in
+// the file at the absolute `path`. Dependencies have files with the same
+// relative path, so only the whole path tells them apart.
+pub fn annotate_asm(block: &str, path: &str) -> Vec<AsmLine> {
+	block
+		.lines()
+		.zip(source_lines(block, path))
+		.map(|(text, source_line)| AsmLine { text: text.to_owned(), source_line })
+		.collect()
+}
+
+// The same lines as `filter_asm` keeps, tagged like `annotate_asm` does.
+// The directives are read before they are filtered out.
+pub fn filter_annotated_asm(block: &str, path: &str) -> Vec<AsmLine> {
+	let lines: Vec<_> = block.lines().collect();
+	let kept: Vec<_> = (0..lines.len()).collect();
+
+	filter_kept_lines(block, &lines, &kept, path)
+}
+
+// The same lines as `extract_asm_function` keeps from demangled
+// assembly, tagged like `annotate_asm` does. The directives of the other
+// functions still count, as a file is only declared where it's first used.
+pub fn annotate_asm_function(block: &str, name: &str, path: &str) -> Option<Vec<AsmLine>> {
+	let lines: Vec<_> = block.lines().collect();
+	let kept = super::functions::asm_function_lines(&lines, name)?;
+
+	Some(filter_kept_lines(block, &lines, &kept, path))
+}
+
+fn filter_kept_lines(block: &str, lines: &[&str], kept: &[usize], path: &str) -> Vec<AsmLine> {
+	let source_lines = source_lines(block, path);
+	let kept_block: String = kept.iter().map(|&index| format!("{}\n", lines[index])).collect();
+
+	super::filter_asm_lines(&kept_block)
+		.into_iter()
+		.map(|line| match line {
+			Some(index) => {
+				let index = kept[index];
+				AsmLine { text: lines[index].to_owned(), source_line: source_lines[index] }
+			}
+			None => AsmLine { text: String::new(), source_line: None },
+		})
+		.collect()
+}
+
+fn source_lines(block: &str, path: &str) -> Vec<Option<u64>> {
+	let mut files = HashMap::new();
+	let mut current = None;
+
+	block
+		.lines()
+		.map(|line| {
+			let trimmed = line.trim_start();
+
+			if is_function_label(line) {
+				// A function doesn't continue the location of the one before
+				current = None;
+			} else if let Some(file) = trimmed.strip_prefix(".file") {
+				if let Some((index, is_path)) = file_entry(file, path) {
+					files.insert(index, is_path);
+				}
+			} else if let Some(loc) = trimmed.strip_prefix(".loc") {
+				current = location(loc, &files);
+			} else if is_instruction(line) {
+				return current;
+			}
+
+			None
+		})
+		.collect()
+}
+
+// Parses ` 2 "/playground" "src/lib.rs" md5 0x...`, where the directory
+// is optional and not used for absolute names
+fn file_entry(file: &str, path: &str) -> Option<(u64, bool)> {
+	let (index, rest) = file.trim_start().split_once(char::is_whitespace)?;
+	let index = index.parse().ok()?;
+
+	let mut names = rest.split('"').skip(1).step_by(2);
+	let (directory, name) = match (names.next()?, names.next()) {
+		(directory, Some(name)) => (Some(directory), name),
+		(name, None) => (None, name),
+	};
+
+	let is_path = match directory {
+		Some(directory) if !name.starts_with('/') => {
+			format!("{}/{}", directory.trim_end_matches('/'), name) == path
+		}
+		_ => name == path,
+	};
+	Some((index, is_path))
+}
+
+// Parses ` 2 3 5 prologue_end`; line 0 means there's no source line
+fn location(loc: &str, files: &HashMap<u64, bool>) -> Option<u64> {
+	let mut columns = loc.split_whitespace();
+	let file = columns.next()?.parse().ok()?;
+	let line = columns.next()?.parse().ok()?;
+
+	match files.get(&file) {
+		Some(true) if line != 0 => Some(line),
+		_ => None,
+	}
+}
+
+fn is_function_label(line: &str) -> bool {
+	!line.starts_with(char::is_whitespace) && line.ends_with(':') && !line.starts_with('.')
+}
+
+fn is_instruction(line: &str) -> bool {
+	let trimmed = line.trim();
+
+	line.starts_with(char::is_whitespace)
+		&& !trimmed.is_empty()
+		&& !trimmed.ends_with(':')
+		&& !trimmed.starts_with(['.', '#', ';'])
+		&& !trimmed.starts_with("//")
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const ASM: &str = r#"	.globl	add
+	.type	add,@function
+add:
+.Lfunc_begin0:
+	.cfi_startproc
+	.file	1 "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860" "library/core/src/num/uint_macros.rs"
+	.loc	1 2533 13 prologue_end
+	lea	eax, [rdi + 2*rdi]
+.Ltmp0:
+	.file	2 "/playground" "src/lib.rs"
+	.loc	2 3 5
+	add	eax, esi
+.Ltmp1:
+	.loc	2 4 2
+	ret
+.Lfunc_end0:
+	.size	add, .Lfunc_end0-add
+	.cfi_endproc
+"#;
+
+	fn tagged(lines: &[AsmLine]) -> Vec<(&str, Option<u64>)> {
+		lines.iter().map(|l| (l.text.trim(), l.source_line)).collect()
+	}
+
+	#[test]
+	fn instructions_tagged_with_lines_from_the_path() {
+		let lines = annotate_asm(ASM, "/playground/src/lib.rs");
+
+		assert_eq!(lines.len(), ASM.lines().count());
+		assert_eq!(
+			tagged(&lines).into_iter().filter(|(_, line)| line.is_some()).collect::<Vec<_>>(),
+			[("add\teax, esi", Some(3)), ("ret", Some(4))]
+		);
+	}
+
+	#[test]
+	fn filtered_lines_keep_their_tags() {
+		assert_eq!(
+			tagged(&filter_annotated_asm(ASM, "/playground/src/lib.rs")),
+			[
+				("", None),
+				("add:", None),
+				("lea\teax, [rdi + 2*rdi]", None),
+				("add\teax, esi", Some(3)),
+				("ret", Some(4)),
+			]
+		);
+	}
+
+	#[test]
+	fn dependencies_and_later_functions_not_tagged() {
+		let asm = [
+			"\t.file\t1 \"/playground/.cargo/registry/src/index/dep-1.0.0\" \"src/lib.rs\"",
+			"\t.file\t2 \"/playground\" \"src/lib.rs\"",
+			"first:",
+			"\t.loc\t2 3 5",
+			"\tret",
+			"second:",
+			"\tnop",
+			"\t.loc\t1 10 1",
+			"\tret",
+		]
+		.join("\n");
+
+		let lines = annotate_asm(&asm, "/playground/src/lib.rs");
+
+		assert_eq!(
+			tagged(&lines).into_iter().filter(|(_, line)| line.is_some()).collect::<Vec<_>>(),
+			[("ret", Some(3))]
+		);
+	}
+
+	#[test]
+	fn function_keeps_files_declared_by_others() {
+		let asm = [
+			"\t.type\tfirst,@function",
+			"first:",
+			"\t.file\t2 \"/playground\" \"src/lib.rs\"",
+			"\t.loc\t2 3 5",
+			"\tret",
+			".Lfunc_end0:",
+			"\t.size\tfirst, .Lfunc_end0-first",
+			"\t.type\tsecond,@function",
+			"second:",
+			"\t.loc\t2 7 5",
+			"\tnop",
+			".Lfunc_end1:",
+			"\t.size\tsecond, .Lfunc_end1-second",
+		]
+		.join("\n");
+
+		let lines = annotate_asm_function(&asm, "second", "/playground/src/lib.rs").unwrap();
+
+		assert_eq!(tagged(&lines), [("", None), ("second:", None), ("nop", Some(7))]);
+		assert_eq!(annotate_asm_function(&asm, "third", "/playground/src/lib.rs"), None);
+	}
+}
@@ -0,0 +1,322 @@
+// Counting what each function of the assembly does, to summarize it next
+// to the listing. Understands x86-64 in either syntax, AArch64, and wasm32.
+
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AsmFunctionStats {
+	/// Demangled, without the hash.
+	pub name: String,
+	pub instructions: usize,
+	pub calls: usize,
+	/// Jumps to labels, conditional or not. Returns and calls aren't
+	/// counted.
+	pub branches: usize,
+	/// Instructions that work on SIMD registers or types.
+	pub vector_instructions: usize,
+	/// Calls to the panics for an out of bounds index or range.
+	pub bounds_checks: usize,
+	/// The bytes the prologue reserves on the stack, including saved
+	/// registers but not the return address.
+	pub stack_frame_size: u64,
+}
+
+// The functions of the assembly, in order, counted after `filter_asm` so
+// that unused code isn't included
+pub fn asm_stats(block: &str) -> Vec<AsmFunctionStats> {
+	lazy_static! {
+		// Example:    .type main,@function
+		static ref FUNCTION_REGEX: Regex = Regex::new(r"^\s+\.type\s*(.*),[@%]function$").unwrap();
+	}
+
+	let functions: HashSet<_> = block
+		.lines()
+		.filter_map(|line| FUNCTION_REGEX.captures(line).and_then(|cap| cap.get(1)))
+		.map(|name| super::demangle_asm(name.as_str()))
+		.collect();
+
+	let filtered = super::demangle_asm(&super::filter_asm(block));
+
+	let mut stats = Vec::new();
+	let mut current: Option<(AsmFunctionStats, Prologue)> = None;
+
+	for line in filtered.lines() {
+		if let Some(label) =
+			line.strip_suffix(':').filter(|_| !line.starts_with(char::is_whitespace))
+		{
+			if functions.contains(label) {
+				stats.extend(current.take().map(|(function, _)| function));
+				let function = AsmFunctionStats { name: label.to_owned(), ..Default::default() };
+				current = Some((function, Prologue::default()));
+			} else if !label.starts_with('.') {
+				// Data that isn't behind a local label
+				stats.extend(current.take().map(|(function, _)| function));
+			}
+			continue;
+		}
+
+		let Some((function, prologue)) = &mut current else { continue };
+		let Some((mnemonic, operands)) = instruction(line) else { continue };
+
+		function.instructions += 1;
+		if is_call(&mnemonic) {
+			function.calls += 1;
+			if is_bounds_check(operands) {
+				function.bounds_checks += 1;
+			}
+		}
+		if is_branch(&mnemonic) {
+			function.branches += 1;
+		}
+		if is_vector(&mnemonic, operands) {
+			function.vector_instructions += 1;
+		}
+		if let Some(bytes) = prologue.reserved(&mnemonic, operands) {
+			function.stack_frame_size += bytes;
+		}
+	}
+	stats.extend(current.map(|(function, _)| function));
+
+	stats
+}
+
+// The statistics of the functions that `extract_asm_function` keeps when
+// asked for `name`
+pub fn asm_function_stats(block: &str, name: &str) -> Vec<AsmFunctionStats> {
+	let mut stats = asm_stats(block);
+	stats.retain(|function| super::functions::same_name(&function.name, name));
+	stats
+}
+
+// The lowercased mnemonic and the rest of an instruction line
+fn instruction(line: &str) -> Option<(String, &str)> {
+	let trimmed = line.trim();
+
+	if !line.starts_with(char::is_whitespace)
+		|| trimmed.is_empty()
+		|| trimmed.starts_with(['.', '#', ';'])
+		|| trimmed.starts_with("//")
+	{
+		return None;
+	}
+
+	let (mnemonic, operands) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+	Some((mnemonic.to_ascii_lowercase(), operands.trim()))
+}
+
+fn is_call(mnemonic: &str) -> bool {
+	matches!(mnemonic, "call" | "callq" | "bl" | "blr" | "call_indirect")
+}
+
+fn is_bounds_check(operands: &str) -> bool {
+	lazy_static! {
+		static ref BOUNDS_CHECK_REGEX: Regex =
+			Regex::new(r"panic_bounds_check|slice_(start_|end_)?index_(len|order)_fail").unwrap();
+	}
+
+	BOUNDS_CHECK_REGEX.is_match(operands)
+}
+
+fn is_branch(mnemonic: &str) -> bool {
+	// x86 `jmp` and `jcc`; AArch64 `b`, `b.cc`, and the compare and test
+	// branches; wasm `br`, `br_if`, and `br_table`
+	mnemonic.starts_with('j')
+		|| mnemonic.starts_with("b.")
+		|| matches!(mnemonic, "b" | "cbz" | "cbnz" | "tbz" | "tbnz" | "br" | "br_if" | "br_table")
+}
+
+fn is_vector(mnemonic: &str, operands: &str) -> bool {
+	lazy_static! {
+		// Example:    paddd xmm0, xmm1
+		static ref X86_VECTOR_REGEX: Regex = Regex::new(r"%?\b[xyz]mm\d+\b").unwrap();
+		// Example:    add v0.4s, v0.4s, v1.4s
+		static ref AARCH64_VECTOR_REGEX: Regex = Regex::new(r"\bv\d+\.\d*[bhsd]\b").unwrap();
+	}
+
+	// Scalar floating point also uses the x86 vector registers
+	let is_scalar_float = mnemonic.ends_with("ss") || mnemonic.ends_with("sd");
+
+	(X86_VECTOR_REGEX.is_match(operands) && !is_scalar_float)
+		|| AARCH64_VECTOR_REGEX.is_match(operands)
+		|| mnemonic.starts_with("v128.")
+		|| ["i8x16.", "i16x8.", "i32x4.", "i64x2.", "f32x4.", "f64x2."]
+			.iter()
+			.any(|prefix| mnemonic.starts_with(prefix))
+}
+
+// Follows the instructions at the start of a function, until the first
+// one that doesn't set up the stack frame
+#[derive(Debug, Default)]
+struct Prologue {
+	done: bool,
+	// wasm adjusts `__stack_pointer` with a constant
+	wasm_stack_pointer: bool,
+}
+
+impl Prologue {
+	fn reserved(&mut self, mnemonic: &str, operands: &str) -> Option<u64> {
+		lazy_static! {
+			// Examples:    sub rsp, 528    subq $528, %rsp    sub sp, sp, #528
+			static ref SUB_REGEX: Regex =
+				Regex::new(r"^(?:rsp, |\$(\w+), %rsp$|sp, sp, #)(\w+)?").unwrap();
+			// Example:    stp x29, x30, [sp, #-32]!
+			static ref PRE_INDEX_REGEX: Regex = Regex::new(r"\[sp, #-(\w+)\]!$").unwrap();
+		}
+
+		if self.done {
+			return None;
+		}
+
+		let operands = operands.to_ascii_lowercase();
+		let operands = operands.as_str();
+
+		match mnemonic {
+			"push" | "pushq" => return Some(8),
+			"sub" | "subq" => {
+				let amount = SUB_REGEX
+					.captures(operands)
+					.and_then(|cap| cap.get(1).or_else(|| cap.get(2)))
+					.and_then(|amount| parse_number(amount.as_str()));
+				if amount.is_some() {
+					return amount;
+				}
+			}
+			"stp" | "str" if operands.contains("[sp") => {
+				return PRE_INDEX_REGEX
+					.captures(operands)
+					.and_then(|cap| parse_number(&cap[1]))
+					.or(Some(0));
+			}
+			"mov" | "movq" if matches!(operands, "rbp, rsp" | "%rsp, %rbp" | "x29, sp") => {
+				return Some(0);
+			}
+			"add" if operands.starts_with("x29, sp") => return Some(0),
+			"global.get" if operands == "__stack_pointer" => {
+				self.wasm_stack_pointer = true;
+				return Some(0);
+			}
+			"i32.const" if self.wasm_stack_pointer => {
+				return parse_number(operands);
+			}
+			"i32.sub" | "local.tee" | "local.set" if self.wasm_stack_pointer => return Some(0),
+			_ => {}
+		}
+
+		self.done = true;
+		None
+	}
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+	match number.strip_prefix("0x") {
+		Some(hex) => u64::from_str_radix(hex, 16).ok(),
+		None => number.parse().ok(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const X86: &str = r#"	.section	.text._ZN2st3big17hf04e0ec126649700E,"ax",@progbits
+	.globl	_ZN2st3big17hf04e0ec126649700E
+	.type	_ZN2st3big17hf04e0ec126649700E,@function
+_ZN2st3big17hf04e0ec126649700E:
+	.cfi_startproc
+	push	rbx
+	.cfi_def_cfa_offset 16
+	sub	rsp, 528
+	mov	rbx, rdi
+	#APP
+	#NO_APP
+	call	qword ptr [rip + _ZN2st6helper17h82251cb3105ff642E@GOTPCREL]
+	add	rsp, 528
+	pop	rbx
+	ret
+.Lfunc_end0:
+	.size	_ZN2st3big17hf04e0ec126649700E, .Lfunc_end0-_ZN2st3big17hf04e0ec126649700E
+	.cfi_endproc
+	.globl	get
+	.type	get,@function
+get:
+	cmp	rdx, rsi
+	jae	.LBB2_2
+	mov	eax, dword ptr [rdi + 4*rdx]
+	ret
+.LBB2_2:
+	push	rax
+	lea	rax, [rip + .Lanon.d31ee3dc0c74c677c9c05b75640c195a.1]
+	call	qword ptr [rip + _RNvNtCsgEmfK2I1SDS_4core9panicking18panic_bounds_check@GOTPCREL]
+.Lfunc_end2:
+	.size	get, .Lfunc_end2-get
+	.globl	sum
+	.type	sum,@function
+sum:
+	pxor	xmm0, xmm0
+.LBB3_6:
+	movdqu	xmm2, xmmword ptr [rdi + rdx]
+	paddd	xmm0, xmm2
+	add	rdx, 32
+	jne	.LBB3_6
+	addss	xmm0, xmm1
+	movd	eax, xmm0
+	ret
+.Lfunc_end3:
+	.size	sum, .Lfunc_end3-sum
+	.type	.Lanon.d31ee3dc0c74c677c9c05b75640c195a.1,@object
+.Lanon.d31ee3dc0c74c677c9c05b75640c195a.1:
+	.asciz	"st.rs"
+"#;
+
+	#[test]
+	fn x86_functions_counted() {
+		assert_eq!(
+			asm_stats(X86),
+			[
+				AsmFunctionStats {
+					name: "st::big".into(),
+					instructions: 7,
+					calls: 1,
+					stack_frame_size: 536,
+					..Default::default()
+				},
+				AsmFunctionStats {
+					name: "get".into(),
+					instructions: 7,
+					calls: 1,
+					branches: 1,
+					bounds_checks: 1,
+					..Default::default()
+				},
+				AsmFunctionStats {
+					name: "sum".into(),
+					instructions: 8,
+					branches: 1,
+					vector_instructions: 4,
+					..Default::default()
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn only_the_requested_function_counted() {
+		let names: Vec<_> = asm_function_stats(X86, "big").into_iter().map(|f| f.name).collect();
+		assert_eq!(names, ["st::big"]);
+	}
+
+	#[test]
+	fn aarch64_prologue_and_vectors() {
+		let asm = "\t.type\tsum,@function\nsum:\n\tstp\tx29, x30, [sp, #-32]!\n\tstr\tx19, [sp, #16]\n\tmov\tx29, sp\n\tsub\tsp, sp, #0x40\n\tadd\tv0.4s, v0.4s, v1.4s\n\tcbz\tx1, .LBB0_2\n\tbl\tother\n.LBB0_2:\n\tret\n.Lfunc_end0:\n\t.size\tsum, .Lfunc_end0-sum\n";
+
+		let stats = asm_stats(asm);
+
+		assert_eq!(stats.len(), 1);
+		assert_eq!(stats[0].stack_frame_size, 96);
+		assert_eq!(stats[0].vector_instructions, 1);
+		assert_eq!((stats[0].calls, stats[0].branches), (1, 1));
+	}
+}
@@ -0,0 +1,480 @@
+// Cleanup and inspection of the WebAssembly text format, as printed by
+// `wasm-tools print`
+
+use std::collections::{HashMap, HashSet};
+
+/// One top-level field of a module, such as a function or an export
+struct Field<'a> {
+	kind: &'a str,
+	lines: Vec<&'a str>,
+}
+
+impl<'a> Field<'a> {
+	fn header(&self) -> &'a str { self.lines[0].trim_start() }
+
+	fn func_name(&self) -> Option<&'a str> {
+		self.header().strip_prefix("(func ").and_then(identifier_or_index)
+	}
+
+	fn body(&self) -> &[&'a str] { &self.lines[1..] }
+}
+
+enum Line<'a> {
+	Field(Field<'a>),
+	Other(&'a str),
+}
+
+// Splits a module into its top-level fields, leaving the surrounding
+// `(module` and `)` lines alone
+fn parse_module(block: &str) -> Vec<Line<'_>> {
+	let mut result = Vec::new();
+	let mut depth = 0;
+	let mut current: Option<Field<'_>> = None;
+
+	for line in block.lines() {
+		let start_depth = depth;
+		depth += paren_depth_change(line);
+
+		match &mut current {
+			Some(field) => field.lines.push(line),
+			None if start_depth == 1 && line.trim_start().starts_with('(') => {
+				let mut kind =
+					line.trim_start()[1..].split(|c: char| c.is_whitespace() || c == ')');
+				let kind = kind.next().unwrap_or_default();
+				current = Some(Field { kind, lines: vec![line] });
+			}
+			None => result.push(Line::Other(line)),
+		}
+
+		if depth <= 1 {
+			if let Some(field) = current.take() {
+				result.push(Line::Field(field));
+			}
+		}
+	}
+
+	result.extend(current.map(Line::Field));
+	result
+}
+
+// Ignores parentheses inside of strings
+fn paren_depth_change(line: &str) -> isize {
+	let mut change = 0;
+	let mut in_string = false;
+	let mut chars = line.chars();
+
+	while let Some(c) = chars.next() {
+		match (in_string, c) {
+			(true, '\\') => {
+				chars.next();
+			}
+			(_, '"') => in_string = !in_string,
+			(false, '(') => change += 1,
+			(false, ')') => change -= 1,
+			(false, ';') if chars.as_str().starts_with(';') => break,
+			_ => (),
+		}
+	}
+
+	change
+}
+
+// Reads a `$name`, `$"quoted name"`, or `(;index;)` at the start of `s`
+fn identifier_or_index(s: &str) -> Option<&str> {
+	identifier(s).or_else(|| {
+		let s = s.strip_prefix("(;")?;
+		s.split_once(";)").map(|(index, _)| index)
+	})
+}
+
+fn identifier(s: &str) -> Option<&str> {
+	let s = s.strip_prefix('$')?;
+
+	if let Some(quoted) = s.strip_prefix('"') {
+		return quoted.find('"').map(|end| &s[..end + 2]);
+	}
+
+	let end = s.find(|c: char| c.is_whitespace() || c == ')' || c == '(').unwrap_or(s.len());
+	Some(&s[..end]).filter(|name| !name.is_empty())
+}
+
+// Every `$name` mentioned in a line
+fn identifiers(line: &str) -> impl Iterator<Item = &str> {
+	line.match_indices('$').filter_map(move |(i, _)| identifier(&line[i..]))
+}
+
+// Functions referenced by an export, an element segment, or the start
+// function are kept, as is everything they call
+fn used_functions<'a>(lines: &[Line<'a>]) -> HashSet<&'a str> {
+	let mut calls: HashMap<&str, Vec<&str>> = HashMap::new();
+	let mut roots = Vec::new();
+
+	for line in lines {
+		let Line::Field(field) = line else { continue };
+
+		match field.kind {
+			"func" => {
+				if let Some(name) = field.func_name() {
+					let callees = field.body().iter().flat_map(|l| identifiers(l));
+					calls.entry(name).or_default().extend(callees);
+				}
+			}
+			"export" | "elem" | "start" => {
+				roots.extend(field.lines.iter().flat_map(|l| identifiers(l)));
+			}
+			_ => (),
+		}
+	}
+
+	let mut used = HashSet::new();
+	while let Some(name) = roots.pop() {
+		if used.insert(name) {
+			roots.extend(calls.get(name).into_iter().flatten());
+		}
+	}
+
+	used
+}
+
+// Removes functions that are never called, exported, or placed in a table
+pub fn filter_wat(block: &str) -> String {
+	let lines = parse_module(block);
+	let used = used_functions(&lines);
+
+	let mut filtered_wat = String::new();
+	for line in &lines {
+		let kept = match line {
+			Line::Field(field) if field.kind == "func" => {
+				field.func_name().is_none_or(|name| used.contains(name))
+			}
+			Line::Field(field) => !field.kind.starts_with('@'),
+			Line::Other(_) => true,
+		};
+
+		if kept {
+			let text = match line {
+				Line::Field(field) => &field.lines[..],
+				Line::Other(line) => std::slice::from_ref(line),
+			};
+			for line in text {
+				filtered_wat.push_str(line);
+				filtered_wat.push('\n');
+			}
+		}
+	}
+
+	filtered_wat
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmImport {
+	pub module: String,
+	pub name: String,
+	pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmExport {
+	pub name: String,
+	pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFunction {
+	pub name: String,
+	pub instructions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmDataSegment {
+	pub name: Option<String>,
+	pub size_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmSummary {
+	pub imports: Vec<WasmImport>,
+	pub exports: Vec<WasmExport>,
+	pub functions: Vec<WasmFunction>,
+	pub data_segments: Vec<WasmDataSegment>,
+}
+
+// Lists what a module imports, exports, defines, and stores in memory
+pub fn summarize_wat(block: &str) -> WasmSummary {
+	let mut summary = WasmSummary::default();
+
+	for line in parse_module(block) {
+		let Line::Field(field) = line else { continue };
+		let header = field.header();
+
+		match field.kind {
+			"import" => {
+				let mut strings = strings(header);
+				let (Some(module), Some(name)) = (strings.next(), strings.next()) else { continue };
+				let kind = nested_kind(header, 2).unwrap_or_default().to_owned();
+				summary.imports.push(WasmImport { module, name, kind });
+			}
+			"export" => {
+				let Some(name) = strings(header).next() else { continue };
+				let kind = nested_kind(header, 1).unwrap_or_default().to_owned();
+				summary.exports.push(WasmExport { name, kind });
+			}
+			"func" => {
+				let name = field.func_name().unwrap_or_default().to_owned();
+				let instructions = field
+					.body()
+					.iter()
+					.map(|l| l.trim())
+					.filter(|l| !l.is_empty() && !l.starts_with('(') && *l != ")")
+					.count();
+				summary.functions.push(WasmFunction { name, instructions });
+			}
+			"data" => {
+				let name = header.strip_prefix("(data ").and_then(identifier).map(str::to_owned);
+				let size_bytes =
+					field.lines.iter().flat_map(|l| string_literals(l)).map(string_size).sum();
+				summary.data_segments.push(WasmDataSegment { name, size_bytes });
+			}
+			_ => (),
+		}
+	}
+
+	summary
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFunctionSize {
+	pub name: String,
+	pub size_bytes: usize,
+}
+
+// Sizes of the functions in a module printed with `--print-offsets`,
+// largest first. The module doesn't say where the last function ends,
+// so it is assumed to end one byte after its last instruction.
+pub fn function_sizes(block: &str) -> Vec<WasmFunctionSize> {
+	const FIELD_KINDS: &[&str] =
+		&["type", "import", "func", "table", "memory", "global", "export", "start", "elem", "data"];
+
+	let mut starts = Vec::new();
+	let mut in_func = false;
+	let mut end = None;
+
+	for line in block.lines() {
+		let Some((offset, rest)) = split_offset(line) else { continue };
+		let rest = rest.trim_start();
+
+		let field_kind = rest.strip_prefix('(').and_then(|r| {
+			let kind = r.split(|c: char| c.is_whitespace() || c == ')').next()?;
+			FIELD_KINDS.contains(&kind).then_some(kind)
+		});
+
+		match field_kind {
+			Some("func") => {
+				let name = rest.strip_prefix("(func ").and_then(identifier_or_index);
+				starts.push((name.unwrap_or_default(), offset));
+				in_func = true;
+				end = Some(offset + 1);
+			}
+			Some(_) => in_func = false,
+			None if in_func => end = Some(offset + 1),
+			None => (),
+		}
+	}
+
+	let ends = starts.iter().skip(1).map(|&(_, offset)| offset).chain(end);
+	let mut sizes: Vec<_> = starts
+		.iter()
+		.zip(ends)
+		.map(|(&(name, start), end)| WasmFunctionSize {
+			name: name.to_owned(),
+			size_bytes: end.saturating_sub(start),
+		})
+		.collect();
+
+	sizes.sort_by_key(|s| std::cmp::Reverse(s.size_bytes));
+	sizes
+}
+
+// Splits `(;@1a2 ;)  (func ...` into the offset and the rest of the line
+fn split_offset(line: &str) -> Option<(usize, &str)> {
+	let line = line.trim_start().strip_prefix("(;@")?;
+	let (offset, rest) = line.split_once(";)")?;
+	let offset = usize::from_str_radix(offset.trim(), 16).ok()?;
+	Some((offset, rest))
+}
+
+// The kind of the first nested field after skipping `n_strings` strings,
+// e.g. `func` in `(export "inc" (func $inc))`
+fn nested_kind(header: &str, n_strings: usize) -> Option<&str> {
+	let mut rest = header;
+	for _ in 0..n_strings {
+		(_, rest) = split_string_literal(rest)?;
+	}
+
+	let rest = &rest[rest.find('(')? + 1..];
+	rest.split(|c: char| c.is_whitespace() || c == ')').next()
+}
+
+// Returns the contents of the next `"..."`, still escaped, and what
+// follows it
+fn split_string_literal(s: &str) -> Option<(&str, &str)> {
+	let start = s.find('"')? + 1;
+	let mut escaped = false;
+	let len = s[start..].find(|c| {
+		let end = c == '"' && !escaped;
+		escaped = c == '\\' && !escaped;
+		end
+	})?;
+	Some((&s[start..start + len], &s[start + len + 1..]))
+}
+
+fn string_literals(line: &str) -> impl Iterator<Item = &str> {
+	let mut rest = line;
+	std::iter::from_fn(move || {
+		let (literal, after) = split_string_literal(rest)?;
+		rest = after;
+		Some(literal)
+	})
+}
+
+fn strings(line: &str) -> impl Iterator<Item = String> + '_ {
+	string_literals(line).map(|s| String::from_utf8_lossy(&unescape(s)).into_owned())
+}
+
+fn string_size(literal: &str) -> usize { unescape(literal).len() }
+
+fn unescape(literal: &str) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	let mut chars = literal.chars();
+
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			let mut buf = [0; 4];
+			bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+			continue;
+		}
+
+		match chars.next() {
+			Some('t') => bytes.push(b'\t'),
+			Some('n') => bytes.push(b'\n'),
+			Some('r') => bytes.push(b'\r'),
+			Some('u') => {
+				let rest = chars.as_str();
+				let code = rest.strip_prefix('{').and_then(|r| r.split_once('}'));
+				if let Some((hex, after)) = code {
+					let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+					let mut buf = [0; 4];
+					bytes.extend(c.unwrap_or_default().encode_utf8(&mut buf).as_bytes());
+					chars = after.chars();
+				}
+			}
+			Some(hi) if hi.is_ascii_hexdigit() => {
+				let lo = chars.next().and_then(|lo| lo.to_digit(16)).unwrap_or(0);
+				bytes.push((hi.to_digit(16).unwrap_or(0) * 16 + lo) as u8);
+			}
+			Some(other) => bytes.push(other as u8),
+			None => (),
+		}
+	}
+
+	bytes
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const MODULE: &str = r#"(module
+  (type (;0;) (func (param i32) (result i32)))
+  (import "env" "log" (func $log (;0;) (type 0)))
+  (func $inc (;1;) (type 0) (param i32) (result i32)
+    (local i32)
+    local.get 0
+    call $helper
+  )
+  (func $helper (;2;) (type 0) (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.add
+  )
+  (func $"core::unused" (;3;) (type 0) (param i32) (result i32)
+    local.get 0
+  )
+  (memory (;0;) 17)
+  (export "memory" (memory 0))
+  (export "inc" (func $inc))
+  (data $.rodata (;0;) (i32.const 1048576) "ab\00\e2\98\83(")
+  (@producers
+    (language "Rust" "")
+  )
+)
+"#;
+
+	#[test]
+	fn unused_functions_removed() {
+		let filtered = filter_wat(MODULE);
+
+		assert!(filtered.contains("(func $inc "));
+		assert!(filtered.contains("(func $helper "));
+		assert!(!filtered.contains("core::unused"));
+		assert!(!filtered.contains("@producers"));
+		assert!(filtered.contains("(data $.rodata"));
+		assert!(filtered.ends_with(")\n"));
+	}
+
+	#[test]
+	fn function_sizes_from_offsets() {
+		let module = r#"(module
+  (;@b     ;)  (type (;0;) (func (param i32) (result i32)))
+  (;@40    ;)  (func $small (;0;) (type 0) (param i32) (result i32)
+  (;@41    ;)    local.get 0
+  (;@43    ;)    local.get 0
+               )
+  (;@46    ;)  (func $big (;1;) (type 0) (param i32) (result i32)
+  (;@47    ;)    (local i32)
+  (;@49    ;)    local.get 0
+  (;@4b    ;)    i32.const 100000
+  (;@50    ;)    i32.add
+               )
+  (;@20    ;)  (export "big" (func $big))
+)
+"#;
+
+		assert_eq!(
+			function_sizes(module),
+			[
+				WasmFunctionSize { name: "big".into(), size_bytes: 11 },
+				WasmFunctionSize { name: "small".into(), size_bytes: 6 },
+			]
+		);
+	}
+
+	#[test]
+	fn module_summarized() {
+		let summary = summarize_wat(MODULE);
+
+		assert_eq!(
+			summary.imports,
+			[WasmImport { module: "env".into(), name: "log".into(), kind: "func".into() }]
+		);
+		assert_eq!(
+			summary.exports,
+			[
+				WasmExport { name: "memory".into(), kind: "memory".into() },
+				WasmExport { name: "inc".into(), kind: "func".into() },
+			]
+		);
+		assert_eq!(
+			summary.functions,
+			[
+				WasmFunction { name: "inc".into(), instructions: 2 },
+				WasmFunction { name: "helper".into(), instructions: 3 },
+				WasmFunction { name: r#""core::unused""#.into(), instructions: 1 },
+			]
+		);
+		assert_eq!(
+			summary.data_segments,
+			[WasmDataSegment { name: Some(".rodata".into()), size_bytes: 7 }]
+		);
+	}
+}
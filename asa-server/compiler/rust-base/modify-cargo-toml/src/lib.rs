@@ -5,6 +5,7 @@ extern crate toml;
 
 use std::collections::BTreeMap;
 
+use toml::value::Table;
 use toml::Value;
 
 type Other = BTreeMap<String, Value>;
@@ -170,3 +171,225 @@ pub fn set_release_lto(cargo_toml: Value, lto: bool) -> Value {
 		cargo_toml
 	})
 }
+
+fn modify_release_profile<F>(cargo_toml: Value, f: F) -> Value
+where
+	F: FnOnce(&mut Other),
+{
+	#[derive(Debug, Serialize, Deserialize)]
+	#[serde(rename_all = "kebab-case")]
+	struct CargoToml {
+		#[serde(default)]
+		profile: Profiles,
+		#[serde(flatten)]
+		other: Other,
+	}
+
+	#[derive(Debug, Default, Serialize, Deserialize)]
+	#[serde(rename_all = "kebab-case")]
+	struct Profiles {
+		#[serde(default)]
+		release: Other,
+		#[serde(flatten)]
+		other: Other,
+	}
+
+	modify(cargo_toml, |mut cargo_toml: CargoToml| {
+		f(&mut cargo_toml.profile.release);
+		cargo_toml
+	})
+}
+
+/// Numeric levels are written as integers, as Cargo requires
+pub fn set_release_opt_level(cargo_toml: Value, opt_level: &str) -> Value {
+	let opt_level = match opt_level.parse() {
+		Ok(level) => Value::Integer(level),
+		Err(_) => Value::String(opt_level.into()),
+	};
+
+	modify_release_profile(cargo_toml, |profile| {
+		profile.insert("opt-level".into(), opt_level);
+	})
+}
+
+pub fn set_release_panic_strategy(cargo_toml: Value, panic: &str) -> Value {
+	modify_release_profile(cargo_toml, |profile| {
+		profile.insert("panic".into(), Value::String(panic.into()));
+	})
+}
+
+pub fn set_release_debug(cargo_toml: Value, debug: bool) -> Value {
+	modify_release_profile(cargo_toml, |profile| {
+		profile.insert("debug".into(), Value::Boolean(debug));
+	})
+}
+
+pub fn set_release_codegen_units(cargo_toml: Value, codegen_units: u32) -> Value {
+	modify_release_profile(cargo_toml, |profile| {
+		profile.insert("codegen-units".into(), Value::Integer(codegen_units.into()));
+	})
+}
+
+fn table_entry<'a>(table: &'a mut Table, key: &str) -> &'a mut Table {
+	let entry = table.entry(key).or_insert_with(|| Value::Table(Table::new()));
+	if !entry.is_table() {
+		*entry = Value::Table(Table::new());
+	}
+	entry.as_table_mut().expect("The entry was just made a table")
+}
+
+fn modify_wasm_pack_release_profile<F>(cargo_toml: Value, f: F) -> Value
+where
+	F: FnOnce(&mut Table),
+{
+	#[derive(Debug, Serialize, Deserialize)]
+	#[serde(rename_all = "kebab-case")]
+	struct CargoToml {
+		package: Package,
+		#[serde(flatten)]
+		other: Other,
+	}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	#[serde(rename_all = "kebab-case")]
+	struct Package {
+		#[serde(default)]
+		metadata: Table,
+		#[serde(flatten)]
+		other: Other,
+	}
+
+	modify(cargo_toml, |mut cargo_toml: CargoToml| {
+		let wasm_pack = table_entry(&mut cargo_toml.package.metadata, "wasm-pack");
+		f(table_entry(table_entry(wasm_pack, "profile"), "release"));
+		cargo_toml
+	})
+}
+
+/// No arguments disables `wasm-opt` completely
+pub fn set_wasm_opt(cargo_toml: Value, args: &[String]) -> Value {
+	let wasm_opt = if args.is_empty() {
+		Value::Boolean(false)
+	} else {
+		Value::Array(args.iter().cloned().map(Value::String).collect())
+	};
+
+	modify_wasm_pack_release_profile(cargo_toml, |profile| {
+		profile.insert("wasm-opt".into(), wasm_opt);
+	})
+}
+
+/// Also turns on debug info in the Cargo profile, and tells `wasm-opt`
+/// to keep it; call it after [`set_wasm_opt`]
+pub fn set_wasm_dwarf_debug_info(cargo_toml: Value, debug_info: bool) -> Value {
+	let cargo_toml = if debug_info { set_release_debug(cargo_toml, true) } else { cargo_toml };
+
+	modify_wasm_pack_release_profile(cargo_toml, |profile| {
+		profile.insert("dwarf-debug-info".into(), Value::Boolean(debug_info));
+		if !debug_info {
+			return;
+		}
+
+		// Otherwise `wasm-opt` throws the DWARF away
+		if let Some(Value::Array(args)) = profile.get_mut("wasm-opt") {
+			let keep_dwarf = Value::String("-g".into());
+			if !args.contains(&keep_dwarf) {
+				args.push(keep_dwarf);
+			}
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const CARGO_TOML: &str = r#"
+		[package]
+		name = "playground"
+		version = "0.0.1"
+		edition = "2021"
+
+		[dependencies]
+		wasm-bindgen = "0.2"
+
+		[profile.release]
+		lto = true
+	"#;
+
+	fn cargo_toml() -> Value { toml::from_str(CARGO_TOML).unwrap() }
+
+	fn release_profile(cargo_toml: &Value) -> &Value { &cargo_toml["profile"]["release"] }
+
+	#[test]
+	fn numeric_opt_level_is_an_integer() {
+		let cargo_toml = set_release_opt_level(cargo_toml(), "3");
+
+		assert_eq!(release_profile(&cargo_toml)["opt-level"], Value::Integer(3));
+	}
+
+	#[test]
+	fn size_opt_level_is_a_string() {
+		let cargo_toml = set_release_opt_level(cargo_toml(), "z");
+
+		assert_eq!(release_profile(&cargo_toml)["opt-level"], Value::String("z".into()));
+	}
+
+	#[test]
+	fn release_profile_setters_keep_the_rest_of_the_manifest() {
+		let cargo_toml = set_release_opt_level(cargo_toml(), "s");
+		let cargo_toml = set_release_panic_strategy(cargo_toml, "abort");
+		let cargo_toml = set_release_codegen_units(cargo_toml, 1);
+
+		let profile = release_profile(&cargo_toml);
+		assert_eq!(profile["opt-level"], Value::String("s".into()));
+		assert_eq!(profile["panic"], Value::String("abort".into()));
+		assert_eq!(profile["codegen-units"], Value::Integer(1));
+		assert_eq!(profile["lto"], Value::Boolean(true));
+
+		assert_eq!(cargo_toml["package"], self::cargo_toml()["package"]);
+		assert_eq!(cargo_toml["dependencies"], self::cargo_toml()["dependencies"]);
+	}
+
+	fn wasm_pack_profile(cargo_toml: &Value) -> &Value {
+		&cargo_toml["package"]["metadata"]["wasm-pack"]["profile"]["release"]
+	}
+
+	#[test]
+	fn wasm_dwarf_debug_info_keeps_the_dwarf() {
+		let cargo_toml = set_wasm_opt(cargo_toml(), &["-Oz".into()]);
+		let cargo_toml = set_wasm_dwarf_debug_info(cargo_toml, true);
+
+		assert_eq!(release_profile(&cargo_toml)["debug"], Value::Boolean(true));
+
+		let profile = wasm_pack_profile(&cargo_toml);
+		assert_eq!(profile["dwarf-debug-info"], Value::Boolean(true));
+		assert_eq!(profile["wasm-opt"], Value::Array(vec!["-Oz".into(), "-g".into()]));
+	}
+
+	#[test]
+	fn wasm_dwarf_debug_info_adds_keep_dwarf_argument_once() {
+		let cargo_toml = set_wasm_opt(cargo_toml(), &["-Oz".into(), "-g".into()]);
+		let cargo_toml = set_wasm_dwarf_debug_info(cargo_toml, true);
+		let cargo_toml = set_wasm_dwarf_debug_info(cargo_toml, true);
+
+		let profile = wasm_pack_profile(&cargo_toml);
+		assert_eq!(profile["wasm-opt"], Value::Array(vec!["-Oz".into(), "-g".into()]));
+	}
+
+	#[test]
+	fn wasm_dwarf_debug_info_leaves_disabled_wasm_opt_alone() {
+		let cargo_toml = set_wasm_opt(cargo_toml(), &[]);
+		let cargo_toml = set_wasm_dwarf_debug_info(cargo_toml, true);
+
+		assert_eq!(wasm_pack_profile(&cargo_toml)["wasm-opt"], Value::Boolean(false));
+	}
+
+	#[test]
+	fn release_profile_is_created_when_missing() {
+		let cargo_toml: Value = toml::from_str("[package]\nname = \"playground\"").unwrap();
+		let cargo_toml = set_release_codegen_units(cargo_toml, 16);
+
+		assert_eq!(release_profile(&cargo_toml)["codegen-units"], Value::Integer(16));
+	}
+}
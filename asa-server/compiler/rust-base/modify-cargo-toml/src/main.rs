@@ -42,6 +42,30 @@ fn main() {
 		cargo_toml = set_release_lto(cargo_toml, lto == "true");
 	}
 
+	if let Ok(opt_level) = env::var("PLAYGROUND_RELEASE_OPT_LEVEL") {
+		cargo_toml = set_release_opt_level(cargo_toml, &opt_level);
+	}
+
+	if let Ok(panic) = env::var("PLAYGROUND_RELEASE_PANIC") {
+		cargo_toml = set_release_panic_strategy(cargo_toml, &panic);
+	}
+
+	if let Some(codegen_units) =
+		env::var("PLAYGROUND_RELEASE_CODEGEN_UNITS").ok().and_then(|c| c.parse().ok())
+	{
+		cargo_toml = set_release_codegen_units(cargo_toml, codegen_units);
+	}
+
+	if let Ok(debug) = env::var("PLAYGROUND_RELEASE_DEBUG") {
+		cargo_toml = set_release_debug(cargo_toml, debug == "true");
+		cargo_toml = set_wasm_dwarf_debug_info(cargo_toml, debug == "true");
+	}
+
+	if let Ok(wasm_opt) = env::var("PLAYGROUND_WASM_OPT") {
+		let args: Vec<_> = wasm_opt.split_whitespace().map(String::from).collect();
+		cargo_toml = set_wasm_opt(cargo_toml, &args);
+	}
+
 	let output = toml::to_string(&cargo_toml).expect("Cannot convert back to TOML");
 
 	fs::write(&output_filename, output)
@@ -5,6 +5,10 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::{fmt, mem, ops};
 
+pub use asm_cleanup::{
+	AsmFunctionStats, AsmLine, AsmPipeline, LineMapping, WasmDataSegment, WasmExport, WasmFunction,
+	WasmFunctionSize, WasmImport, WasmSummary,
+};
 use futures::future::{BoxFuture, OptionFuture};
 use futures::stream::BoxStream;
 use futures::{Future, FutureExt, Stream, StreamExt};
@@ -23,10 +27,13 @@ use tracing::{info_span, instrument, trace, trace_span, warn, Instrument};
 
 use crate::coordinator::compile_error::CodeNotUtf8Snafu;
 use crate::message::{
-	CommandStatistics, CoordinatorMessage, DeleteFileRequest, ExecuteCommandRequest,
-	ExecuteCommandResponse, JobId, Multiplexed, OneToOneResponse, ReadFileRequest,
-	ReadFileResponse, SerializedError2, WorkerMessage, WriteFileRequest,
+	Capability, CommandStatistics, CoordinatorMessage, CreateProjectRequest, DeleteFileRequest,
+	ExecuteCommandRequest, ExecuteCommandResponse, HelloRequest, HelloResponse, JobId, Multiplexed,
+	OneToOneResponse, ReadFileRequest, ReadFileResponse, ReadFilesRequest, ReadFilesResponse,
+	RemoveDirectoryRequest, SerializedError2, StatRequest, StatResponse, WorkerBuildInfo,
+	WorkerMessage, WriteFileRequest, PROTOCOL_VERSION,
 };
+pub use crate::message::{FileContent, OutputChunk, OutputLimit, OutputLimitMode};
 use crate::{bincode_input_closed, DropErrorDetailsExt};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -158,6 +165,8 @@ pub struct Crate {
 	pub name: String,
 	pub version: String,
 	pub id: String,
+	/// Whether the crate can be used in code built for wasm.
+	pub wasm: bool,
 }
 
 #[derive(Deserialize)]
@@ -165,12 +174,17 @@ struct InternalCrate {
 	name: String,
 	version: String,
 	id: String,
+	// Older lists didn't say
+	#[serde(default = "default_wasm")]
+	wasm: bool,
 }
 
+fn default_wasm() -> bool { true }
+
 impl From<InternalCrate> for Crate {
 	fn from(other: InternalCrate) -> Self {
-		let InternalCrate { name, version, id } = other;
-		Self { name, version, id }
+		let InternalCrate { name, version, id, wasm } = other;
+		Self { name, version, id, wasm }
 	}
 }
 
@@ -201,6 +215,36 @@ pub enum AssemblyFlavor {
 	Intel,
 }
 
+/// The instruction set to show assembly for.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum AssemblyArchitecture {
+	/// Whatever the container runs on, usually x86_64.
+	#[default]
+	Host,
+	Aarch64,
+	Wasm32,
+}
+
+impl AssemblyArchitecture {
+	pub(crate) fn to_rustc_target(self) -> Option<&'static str> {
+		use AssemblyArchitecture::*;
+
+		match self {
+			Host => None,
+			Aarch64 => Some("aarch64-unknown-linux-gnu"),
+			Wasm32 => Some("wasm32-unknown-unknown"),
+		}
+	}
+
+	// Only the assembly is used and there's no linker for the other
+	// targets, so binaries are "linked" by a command that does nothing
+	pub(crate) fn linker_env(self) -> Option<(String, String)> {
+		let target = self.to_rustc_target()?;
+		let name = format!("CARGO_TARGET_{}_LINKER", target.to_uppercase().replace('-', "_"));
+		Some((name, "true".to_owned()))
+	}
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DemangleAssembly {
 	Demangle,
@@ -220,6 +264,12 @@ pub enum CompileTarget {
 	LlvmIr,
 	Mir,
 	Wasm,
+	/// Runs the `wasm-bindgen-test` tests in Node instead of producing
+	/// a package.
+	WasmTest,
+	/// The demangled text format of the module, without running
+	/// `wasm-bindgen`.
+	WasmText(ProcessAssembly),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -379,15 +429,27 @@ pub struct ExecuteRequest {
 	pub tests: bool,
 	pub backtrace: bool,
 	pub code: String,
+	pub package_name: String,
+	/// How often [`ActiveExecution::status_rx`] is updated; `None`
+	/// means every second.
+	pub statistics_interval: Option<Duration>,
+	/// `None` means the worker's default limit.
+	pub output_limit: Option<OutputLimit>,
 }
 
 impl ExecuteRequest {
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		write_primary_file_request(&self.project_dir(), self.crate_type, &self.code)
 	}
 
 	fn execute_cargo_request(&self) -> ExecuteCommandRequest {
@@ -413,7 +475,9 @@ impl ExecuteRequest {
 			cmd: "cargo".to_owned(),
 			args: args.into_iter().map(|s| s.to_owned()).collect(),
 			envs,
-			cwd: None,
+			cwd: Some(self.project_dir()),
+			statistics_interval: self.statistics_interval,
+			output_limit: self.output_limit,
 		}
 	}
 }
@@ -429,20 +493,55 @@ impl CargoTomlModifier for ExecuteRequest {
 		if let Some(crate_type) = self.crate_type.to_library_cargo_toml_key() {
 			cargo_toml = modify_cargo_toml::set_crate_type(cargo_toml, crate_type);
 		}
-		cargo_toml
+
+		modify_cargo_toml::set_name(cargo_toml, self.package_name.as_str())
 	}
+
+	fn project_dir(&self) -> String { ExecuteRequest::project_dir(self) }
 }
 
+/// Statistics that aren't available on the current platform are `None`.
 #[derive(Debug, Clone)]
 pub struct ExecuteStatus {
 	pub resident_set_size_bytes: u64,
+	pub peak_resident_set_size_bytes: u64,
 	pub total_time_secs: f64,
+	pub user_time_secs: f64,
+	pub system_time_secs: f64,
+	pub voluntary_context_switches: Option<u64>,
+	pub involuntary_context_switches: Option<u64>,
+	pub read_bytes: Option<u64>,
+	pub written_bytes: Option<u64>,
+	pub threads: Option<u64>,
 }
 
 impl From<CommandStatistics> for ExecuteStatus {
 	fn from(value: CommandStatistics) -> Self {
-		let CommandStatistics { total_time_secs, resident_set_size_bytes } = value;
-		Self { resident_set_size_bytes, total_time_secs }
+		let CommandStatistics {
+			total_time_secs,
+			user_time_secs,
+			system_time_secs,
+			resident_set_size_bytes,
+			peak_resident_set_size_bytes,
+			voluntary_context_switches,
+			involuntary_context_switches,
+			read_bytes,
+			written_bytes,
+			threads,
+		} = value;
+
+		Self {
+			resident_set_size_bytes,
+			peak_resident_set_size_bytes,
+			total_time_secs,
+			user_time_secs,
+			system_time_secs,
+			voluntary_context_switches,
+			involuntary_context_switches,
+			read_bytes,
+			written_bytes,
+			threads,
+		}
 	}
 }
 
@@ -450,6 +549,8 @@ impl From<CommandStatistics> for ExecuteStatus {
 pub struct ExecuteResponse {
 	pub success: bool,
 	pub exit_detail: String,
+	/// The last statistics gathered before the program exited.
+	pub statistics: Option<ExecuteStatus>,
 }
 
 #[derive(Debug, Clone)]
@@ -460,27 +561,171 @@ pub struct CompileRequest {
 	pub mode: Mode,
 	pub code: String,
 	pub package_name: String,
+	/// `None` means the worker's default limit.
+	pub output_limit: Option<OutputLimit>,
+	pub release_profile: ReleaseProfile,
+	/// Report which lines of the code each part of the output came
+	/// from. Only used by [`CompileTarget::Wasm`], which keeps DWARF in
+	/// the module, and [`CompileTarget::Assembly`].
+	pub debug_info: bool,
+	/// Add a module that lets the page receive panics and printed
+	/// output; see `wasm-prelude.rs`.
+	pub console_prelude: bool,
+	/// Only show this item, such as `greet` or `impl Foo`, and what the
+	/// compiler generated for it. For [`CompileTarget::Assembly`],
+	/// [`CompileTarget::LlvmIr`], and [`CompileTarget::Mir`], this is a
+	/// function name, such as `add` or `Foo::get`, and the data it uses
+	/// is shown too. Assembly is always demangled when this is set.
+	/// Everything is shown when the item can't be found.
+	pub item: Option<String>,
+	/// Cleanup for [`CompileTarget::Assembly`], on top of the filtering
+	/// and demangling that the target asks for.
+	pub asm_pipeline: AsmPipeline,
+	/// Only used by [`CompileTarget::Assembly`]. The flavor only applies
+	/// to x86 hosts.
+	pub assembly_architecture: AssemblyArchitecture,
+}
+
+/// Settings that trade compile time and features for smaller or faster
+/// code. Only used by release builds.
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseProfile {
+	pub opt_level: Option<OptLevel>,
+	pub panic_abort: bool,
+	pub codegen_units: Option<u32>,
+	/// Arguments for `wasm-opt`, such as `-Oz`. `wasm-opt` isn't run
+	/// when this is empty.
+	pub wasm_opt: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OptLevel {
+	Zero,
+	One,
+	Two,
+	Three,
+	Size,
+	MinSize,
+}
+
+impl OptLevel {
+	pub(crate) fn to_cargo_toml_key(self) -> &'static str {
+		use OptLevel::*;
+
+		match self {
+			Zero => "0",
+			One => "1",
+			Two => "2",
+			Three => "3",
+			Size => "s",
+			MinSize => "z",
+		}
+	}
 }
 
 impl CompileRequest {
+	/// Where wasm-pack puts the module and its JavaScript bindings
+	const PACKAGE_DIR: &'static str = "pkg";
+	const PRELUDE: &'static str = include_str!("../wasm-prelude.rs");
+	const PRELUDE_MODULE: &'static str = "asa_prelude";
+
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		// The macros are only in scope for the code after the module, so
+		// it goes first. It doesn't get its own line so that the line
+		// numbers of the code don't change.
+		let code = if self.console_prelude {
+			let (attributes, code) = self.code.split_at(inner_attributes_end(&self.code));
+			format!("{attributes}#[macro_use] mod {}; {code}", Self::PRELUDE_MODULE)
+		} else {
+			self.code.clone()
+		};
+
+		write_primary_file_request(&self.project_dir(), self.crate_type, &code)
+	}
+
+	pub(crate) fn write_prelude_request(&self) -> Option<WriteFileRequest> {
+		self.console_prelude.then(|| WriteFileRequest {
+			path: self.project_path(&format!("src/{}.rs", Self::PRELUDE_MODULE)),
+			content: Self::PRELUDE.into(),
+		})
+	}
+
+	pub(crate) fn read_output_request(&self, output_path: &str) -> ReadFileRequest {
+		ReadFileRequest { path: self.project_path(output_path) }
+	}
+
+	pub(crate) fn remove_package_request(&self) -> RemoveDirectoryRequest {
+		RemoveDirectoryRequest { path: self.project_path(Self::PACKAGE_DIR) }
+	}
+
+	pub(crate) fn read_package_request(&self) -> ReadFilesRequest {
+		ReadFilesRequest { paths: vec![self.project_path(Self::PACKAGE_DIR)] }
 	}
 
+	/// Makes the paths of the package's files relative to the package
+	/// directory.
+	pub(crate) fn package_files(&self, files: Vec<FileContent>) -> Vec<FileContent> {
+		let package_dir = self.project_path(&format!("{}/", Self::PACKAGE_DIR));
+
+		files
+			.into_iter()
+			.filter_map(|FileContent { path, content }| {
+				let path = path.strip_prefix(&package_dir)?.to_owned();
+				Some(FileContent { path, content })
+			})
+			.collect()
+	}
+
+	pub(crate) fn stat_module_request(&self, output_path: &str) -> StatRequest {
+		StatRequest { path: self.project_path(&format!("{output_path}.wasm")) }
+	}
+
+	pub(crate) fn read_module_offsets_request(&self, output_path: &str) -> ReadFileRequest {
+		ReadFileRequest { path: self.project_path(&format!("{output_path}.sizes.wat")) }
+	}
+
+	pub(crate) fn read_line_table_request(&self, output_path: &str) -> ReadFileRequest {
+		ReadFileRequest { path: self.project_path(&format!("{output_path}.debug-line")) }
+	}
+
+	pub(crate) fn read_sections_request(&self, output_path: &str) -> ReadFileRequest {
+		ReadFileRequest { path: self.project_path(&format!("{output_path}.objdump")) }
+	}
+
+	fn project_path(&self, path: &str) -> String { format!("{}/{path}", self.project_dir()) }
+
+	/// The code's file, relative to the worker's project directory
+	pub(crate) fn source_path(&self) -> String { self.project_path(self.crate_type.primary_path()) }
+
 	pub(crate) fn execute_cargo_request(&self, output_path: &str) -> ExecuteCommandRequest {
 		use CompileTarget::*;
 
-		let mut args = if let Wasm = self.target { vec!["wasm"] } else { vec!["rustc"] };
+		let mut args = match self.target {
+			Wasm => vec!["wasm"],
+			WasmTest => vec!["wasm-test"],
+			WasmText(_) => vec!["wasm-text"],
+			_ => vec!["rustc"],
+		};
 		if let Mode::Release = self.mode {
 			args.push("--release");
 		}
 
 		match self.target {
 			Assembly(flavor, _, _) => {
+				if let Some(target) = self.assembly_architecture.to_rustc_target() {
+					args.extend(&["--target", target]);
+				}
+
 				args.extend(&["--", "--emit", "asm=compilation"]);
 
 				// Enable extra assembly comments for nightly builds
@@ -491,36 +736,101 @@ impl CompileRequest {
 					}
 				}
 
-				args.push("-C");
-				match flavor {
-					AssemblyFlavor::Att => args.push("llvm-args=-x86-asm-syntax=att"),
-					AssemblyFlavor::Intel => args.push("llvm-args=-x86-asm-syntax=intel"),
+				if self.assembly_architecture == AssemblyArchitecture::Host {
+					args.push("-C");
+					match flavor {
+						AssemblyFlavor::Att => args.push("llvm-args=-x86-asm-syntax=att"),
+						AssemblyFlavor::Intel => args.push("llvm-args=-x86-asm-syntax=intel"),
+					}
+				}
+
+				// Emits the `.loc` directives that tie instructions to lines
+				if self.debug_info {
+					args.extend(&["-C", "debuginfo=line-tables-only"]);
 				}
 			}
 			LlvmIr => args.extend(&["--", "--emit", "llvm-ir=compilation"]),
 			Mir => args.extend(&["--", "--emit", "mir=compilation"]),
 			Hir => args.extend(&["--", "-Zunpretty=hir", "-o", output_path]),
-			Wasm => args.extend(&["-o", output_path]),
+			Wasm => {
+				if self.debug_info {
+					args.push("--line-table");
+				}
+				// Lets the script fail instead of wasm-pack skipping it
+				if !self.release_profile.wasm_opt.is_empty() {
+					args.push("--wasm-opt");
+				}
+				args.extend(&["-o", output_path]);
+			}
+			WasmTest | WasmText(_) => args.extend(&["-o", output_path]),
+		}
+
+		let mut envs = HashMap::new();
+		if let Assembly(..) = self.target {
+			envs.extend(self.assembly_architecture.linker_env());
 		}
-		let envs = HashMap::new();
 
 		ExecuteCommandRequest {
 			cmd: "cargo".to_owned(),
 			args: args.into_iter().map(|s| s.to_owned()).collect(),
 			envs,
-			cwd: None,
+			cwd: Some(self.project_dir()),
+			statistics_interval: None,
+			output_limit: self.output_limit,
 		}
 	}
 
+	// The steps for the assembly, including the ones that the target asks for
+	fn assembly_pipeline(&self) -> Option<AsmPipeline> {
+		let CompileTarget::Assembly(_, demangle, process) = self.target else {
+			return None;
+		};
+
+		let mut pipeline = self.asm_pipeline;
+		pipeline.filter |= process == ProcessAssembly::Filter;
+		pipeline.demangle |= demangle == DemangleAssembly::Demangle;
+		pipeline.strip_hashes |= demangle == DemangleAssembly::Demangle;
+		Some(pipeline)
+	}
+
 	pub(crate) fn postprocess_result(&self, code: CompiledCode) -> CompiledCode {
+		if let (CompiledCode::CodeStr(code_str), Some(item)) = (&code, &self.item) {
+			let function = match self.target {
+				CompileTarget::LlvmIr => asm_cleanup::extract_llvm_ir_function(code_str, item),
+				CompileTarget::Mir => asm_cleanup::extract_mir_function(code_str, item),
+				_ => None,
+			};
+
+			if let Some(function) = function {
+				return CompiledCode::CodeStr(function);
+			}
+		}
+
 		if let CompiledCode::CodeStr(code_str) = &code {
-			if let CompileTarget::Assembly(_, demangle, process) = self.target {
-				if demangle == DemangleAssembly::Demangle {
-					return CompiledCode::CodeStr(asm_cleanup::demangle_asm(code_str));
-				}
+			if let Some(pipeline) = self.assembly_pipeline() {
+				// Functions are found by their demangled names and come
+				// out filtered
+				let function = self.item.as_ref().and_then(|item| {
+					asm_cleanup::extract_asm_function(&asm_cleanup::demangle_asm(code_str), item)
+				});
+
+				let asm = match function {
+					Some(function) => asm_cleanup::process_asm(
+						&function,
+						AsmPipeline { filter: false, demangle: false, ..pipeline },
+					),
+					None => asm_cleanup::process_asm(code_str, pipeline),
+				};
+				return CompiledCode::CodeStr(asm);
+			}
+
+			if let CompileTarget::WasmText(ProcessAssembly::Filter) = self.target {
+				return CompiledCode::CodeStr(asm_cleanup::filter_wat(code_str));
+			}
 
-				if process == ProcessAssembly::Filter {
-					return CompiledCode::CodeStr(asm_cleanup::filter_asm(code_str));
+			if let (CompileTarget::Hir, Some(item)) = (self.target, &self.item) {
+				if let Some(hir) = asm_cleanup::extract_item(code_str, item) {
+					return CompiledCode::CodeStr(hir);
 				}
 			}
 		}
@@ -546,6 +856,27 @@ impl CargoTomlModifier for CompileRequest {
 
 			if CompileTarget::Wasm == self.target {
 				cargo_toml = modify_cargo_toml::set_release_lto(cargo_toml, true);
+
+				cargo_toml =
+					modify_cargo_toml::set_wasm_opt(cargo_toml, &self.release_profile.wasm_opt);
+				if self.debug_info {
+					cargo_toml = modify_cargo_toml::set_wasm_dwarf_debug_info(cargo_toml, true);
+				}
+			}
+
+			let ReleaseProfile { opt_level, panic_abort, codegen_units, .. } = self.release_profile;
+			if let Some(opt_level) = opt_level {
+				cargo_toml = modify_cargo_toml::set_release_opt_level(
+					cargo_toml,
+					opt_level.to_cargo_toml_key(),
+				);
+			}
+			if panic_abort {
+				cargo_toml = modify_cargo_toml::set_release_panic_strategy(cargo_toml, "abort");
+			}
+			if let Some(codegen_units) = codegen_units {
+				cargo_toml =
+					modify_cargo_toml::set_release_codegen_units(cargo_toml, codegen_units);
 			}
 
 			cargo_toml
@@ -553,12 +884,17 @@ impl CargoTomlModifier for CompileRequest {
 			panic!("Invalid language: {:?}", self.language)
 		}
 	}
+
+	fn project_dir(&self) -> String { CompileRequest::project_dir(self) }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub enum CompiledCode {
 	CodeStr(String),
 	CodeBin(Vec<u8>),
+	/// The files wasm-pack generated for [`CompileTarget::Wasm`], with
+	/// paths relative to the package directory.
+	Package(Vec<FileContent>),
 }
 impl CompiledCode {
 	pub fn from_bytes(source: Vec<u8>) -> Self {
@@ -574,6 +910,73 @@ pub struct CompileResponse {
 	pub success: bool,
 	pub exit_detail: String,
 	pub code: CompiledCode,
+	/// Only filled in for [`CompileTarget::WasmTest`].
+	pub tests: Vec<TestResult>,
+	/// Only filled in for [`CompileTarget::WasmText`]; describes the
+	/// whole module, even when unused functions are filtered out.
+	pub wasm_summary: Option<WasmSummary>,
+	/// Only filled in for a successful [`CompileTarget::Wasm`].
+	pub size_report: Option<WasmSizeReport>,
+	/// Only filled in for a successful [`CompileTarget::Wasm`] that
+	/// asked for [`CompileRequest::debug_info`].
+	pub line_map: Option<WasmLineMap>,
+	/// Only filled in for [`CompileTarget::Assembly`] that asked for
+	/// [`CompileRequest::debug_info`]. The same lines as the code, each
+	/// tagged with the line of the code it came from.
+	pub asm_lines: Option<Vec<AsmLine>>,
+	/// Only filled in for a successful [`CompileTarget::Assembly`]. One
+	/// entry for each function, in the order of the unfiltered assembly,
+	/// or only for [`CompileRequest::item`] when it was found.
+	pub asm_stats: Option<Vec<AsmFunctionStats>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WasmLineMap {
+	/// Addresses are byte offsets into the module, as shown in browser
+	/// stack traces, and are ordered. Each line of the code covers the
+	/// bytes up to the next entry.
+	pub lines: Vec<LineMapping>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WasmSizeReport {
+	/// The module after `wasm-opt`, without the JavaScript glue.
+	pub total_bytes: u64,
+	/// Largest first.
+	pub functions: Vec<WasmFunctionSize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestResult {
+	pub name: String,
+	pub outcome: TestOutcome,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOutcome {
+	Passed,
+	Failed,
+	Ignored,
+}
+
+/// Picks the `test <name> ... <outcome>` lines out of the test
+/// runner's output.
+fn parse_test_results(output: &str) -> Vec<TestResult> {
+	output
+		.lines()
+		.filter_map(|line| {
+			let line = line.strip_prefix("test ")?;
+			let (name, outcome) = line.rsplit_once(" ... ")?;
+			let outcome = match outcome.trim() {
+				"ok" => TestOutcome::Passed,
+				"FAIL" | "FAILED" => TestOutcome::Failed,
+				o if o.starts_with("ignored") => TestOutcome::Ignored,
+				_ => return None,
+			};
+			Some(TestResult { name: name.to_owned(), outcome })
+		})
+		.collect()
 }
 
 #[derive(Debug, Clone)]
@@ -582,15 +985,28 @@ pub struct FormatRequest {
 	pub crate_type: CrateType,
 	pub edition: RustEdition,
 	pub code: String,
+	pub package_name: String,
 }
 
 impl FormatRequest {
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		write_primary_file_request(&self.project_dir(), self.crate_type, &self.code)
+	}
+
+	pub(crate) fn read_main_request(&self) -> ReadFileRequest {
+		ReadFileRequest {
+			path: format!("{}/{}", self.project_dir(), self.crate_type.primary_path()),
+		}
 	}
 
 	pub(crate) fn execute_cargo_request(&self) -> ExecuteCommandRequest {
@@ -598,7 +1014,9 @@ impl FormatRequest {
 			cmd: "cargo".to_owned(),
 			args: vec!["fmt".to_owned()],
 			envs: Default::default(),
-			cwd: None,
+			cwd: Some(self.project_dir()),
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 }
@@ -614,8 +1032,11 @@ impl CargoTomlModifier for FormatRequest {
 		if let Some(crate_type) = self.crate_type.to_library_cargo_toml_key() {
 			cargo_toml = modify_cargo_toml::set_crate_type(cargo_toml, crate_type);
 		}
-		cargo_toml
+
+		modify_cargo_toml::set_name(cargo_toml, self.package_name.as_str())
 	}
+
+	fn project_dir(&self) -> String { FormatRequest::project_dir(self) }
 }
 
 #[derive(Debug, Clone)]
@@ -631,15 +1052,22 @@ pub struct ClippyRequest {
 	pub crate_type: CrateType,
 	pub edition: RustEdition,
 	pub code: String,
+	pub package_name: String,
 }
 
 impl ClippyRequest {
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		write_primary_file_request(&self.project_dir(), self.crate_type, &self.code)
 	}
 
 	pub(crate) fn execute_cargo_request(&self) -> ExecuteCommandRequest {
@@ -647,7 +1075,9 @@ impl ClippyRequest {
 			cmd: "cargo".to_owned(),
 			args: vec!["clippy".to_owned()],
 			envs: Default::default(),
-			cwd: None,
+			cwd: Some(self.project_dir()),
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 }
@@ -663,8 +1093,11 @@ impl CargoTomlModifier for ClippyRequest {
 		if let Some(crate_type) = self.crate_type.to_library_cargo_toml_key() {
 			cargo_toml = modify_cargo_toml::set_crate_type(cargo_toml, crate_type);
 		}
-		cargo_toml
+
+		modify_cargo_toml::set_name(cargo_toml, self.package_name.as_str())
 	}
+
+	fn project_dir(&self) -> String { ClippyRequest::project_dir(self) }
 }
 
 #[derive(Debug, Clone)]
@@ -678,24 +1111,95 @@ pub struct MiriRequest {
 	pub channel: RustChannel,
 	pub crate_type: CrateType,
 	pub edition: RustEdition,
+	/// Run the crate's tests instead of `main`. Libraries have no
+	/// `main`, so their tests are always run.
+	pub tests: bool,
+	pub flags: MiriFlags,
 	pub code: String,
+	pub package_name: String,
+}
+
+/// Options for Miri, passed on through `MIRIFLAGS`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MiriFlags {
+	/// Keeps the program from reading the clock, the environment, or
+	/// files. Off by default, so that such programs run at all.
+	pub isolation: bool,
+	pub aliasing_model: AliasingModel,
+	pub strict_provenance: bool,
+	/// Seeds Miri's random choices, such as allocation addresses and
+	/// thread scheduling.
+	pub seed: Option<u64>,
+	/// Runs the program once for each seed in `0..many_seeds`,
+	/// stopping at the first one that fails.
+	pub many_seeds: Option<u64>,
+}
+
+impl MiriFlags {
+	pub(crate) fn to_miri_flags(&self) -> Vec<String> {
+		let mut flags = Vec::new();
+
+		if !self.isolation {
+			flags.push("-Zmiri-disable-isolation".to_owned());
+		}
+
+		if let AliasingModel::TreeBorrows = self.aliasing_model {
+			flags.push("-Zmiri-tree-borrows".to_owned());
+		}
+
+		if self.strict_provenance {
+			flags.push("-Zmiri-strict-provenance".to_owned());
+		}
+
+		if let Some(seed) = self.seed {
+			flags.push(format!("-Zmiri-seed={seed}"));
+		}
+
+		if let Some(many_seeds) = self.many_seeds {
+			flags.push(format!("-Zmiri-many-seeds=0..{many_seeds}"));
+		}
+
+		flags
+	}
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum AliasingModel {
+	#[default]
+	StackedBorrows,
+	TreeBorrows,
 }
 
 impl MiriRequest {
+	pub(crate) fn runs_tests(&self) -> bool { self.tests || !self.crate_type.is_binary() }
+
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		write_primary_file_request(&self.project_dir(), self.crate_type, &self.code)
 	}
 
 	pub(crate) fn execute_cargo_request(&self) -> ExecuteCommandRequest {
+		let subcommand = if self.runs_tests() { "test" } else { "run" };
+
+		let mut envs = HashMap::new();
+		envs.insert("PLAYGROUND_MIRI_FLAGS".to_owned(), self.flags.to_miri_flags().join(" "));
+
 		ExecuteCommandRequest {
 			cmd: "cargo".to_owned(),
-			args: vec!["miri-playground".to_owned()],
-			envs: Default::default(),
-			cwd: None,
+			args: vec!["miri-playground".to_owned(), subcommand.to_owned()],
+			envs,
+			cwd: Some(self.project_dir()),
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 }
@@ -711,8 +1215,11 @@ impl CargoTomlModifier for MiriRequest {
 		if let Some(crate_type) = self.crate_type.to_library_cargo_toml_key() {
 			cargo_toml = modify_cargo_toml::set_crate_type(cargo_toml, crate_type);
 		}
-		cargo_toml
+
+		modify_cargo_toml::set_name(cargo_toml, self.package_name.as_str())
 	}
+
+	fn project_dir(&self) -> String { MiriRequest::project_dir(self) }
 }
 
 #[derive(Debug, Clone)]
@@ -726,16 +1233,27 @@ pub struct MacroExpansionRequest {
 	pub channel: RustChannel,
 	pub crate_type: CrateType,
 	pub edition: RustEdition,
+	/// Only show the expansion of this item, such as `greet` or `impl
+	/// Foo`, and what the compiler generated for it. The whole crate is
+	/// shown when the item can't be found.
+	pub item: Option<String>,
 	pub code: String,
+	pub package_name: String,
 }
 
 impl MacroExpansionRequest {
+	pub(crate) fn project_dir(&self) -> String { package_project_dir(&self.package_name) }
+
+	pub(crate) fn create_project_request(&self) -> CreateProjectRequest {
+		CreateProjectRequest { path: self.project_dir() }
+	}
+
 	pub(crate) fn delete_previous_main_request(&self) -> DeleteFileRequest {
-		delete_previous_primary_file_request(self.crate_type)
+		delete_previous_primary_file_request(&self.project_dir(), self.crate_type)
 	}
 
 	pub(crate) fn write_main_request(&self) -> WriteFileRequest {
-		write_primary_file_request(self.crate_type, &self.code)
+		write_primary_file_request(&self.project_dir(), self.crate_type, &self.code)
 	}
 
 	pub(crate) fn execute_cargo_request(&self) -> ExecuteCommandRequest {
@@ -743,7 +1261,9 @@ impl MacroExpansionRequest {
 			cmd: "cargo".to_owned(),
 			args: ["rustc", "--", "-Zunpretty=expanded"].map(str::to_owned).to_vec(),
 			envs: Default::default(),
-			cwd: None,
+			cwd: Some(self.project_dir()),
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 }
@@ -759,8 +1279,11 @@ impl CargoTomlModifier for MacroExpansionRequest {
 		if let Some(crate_type) = self.crate_type.to_library_cargo_toml_key() {
 			cargo_toml = modify_cargo_toml::set_crate_type(cargo_toml, crate_type);
 		}
-		cargo_toml
+
+		modify_cargo_toml::set_name(cargo_toml, self.package_name.as_str())
 	}
+
+	fn project_dir(&self) -> String { MacroExpansionRequest::project_dir(self) }
 }
 
 #[derive(Debug, Clone)]
@@ -777,18 +1300,19 @@ pub struct WithOutput<T> {
 }
 
 impl<T> WithOutput<T> {
-	async fn try_absorb<F, E>(
+	async fn try_absorb<F, E, O>(
 		task: F,
-		stdout_rx: mpsc::Receiver<String>,
-		stderr_rx: mpsc::Receiver<String>,
+		stdout_rx: mpsc::Receiver<O>,
+		stderr_rx: mpsc::Receiver<O>,
 	) -> Result<WithOutput<T>, E>
 	where
 		F: Future<Output = Result<T, E>>,
+		O: Into<String>,
 	{
 		Self::try_absorb_stream(
 			task,
-			ReceiverStream::new(stdout_rx),
-			ReceiverStream::new(stderr_rx),
+			ReceiverStream::new(stdout_rx).map(Into::into),
+			ReceiverStream::new(stderr_rx).map(Into::into),
 		)
 		.await
 	}
@@ -817,12 +1341,78 @@ impl<T> ops::Deref for WithOutput<T> {
 	fn deref(&self) -> &Self::Target { &self.response }
 }
 
-fn write_primary_file_request(crate_type: CrateType, code: &str) -> WriteFileRequest {
-	WriteFileRequest { path: crate_type.primary_path().to_owned(), content: code.into() }
+// Where the inner attributes and doc comments at the start of `code` end,
+// which is the first place an item may go
+fn inner_attributes_end(code: &str) -> usize {
+	let mut offset = 0;
+	let mut end = 0;
+
+	loop {
+		let rest = code[offset..].trim_start();
+		offset = code.len() - rest.len();
+
+		let len = if let Some(attribute) = rest.strip_prefix("#![") {
+			let (mut depth, mut in_string, mut escaped) = (1, false, false);
+			let close = attribute.find(|c| {
+				if in_string {
+					match c {
+						_ if escaped => escaped = false,
+						'\\' => escaped = true,
+						'"' => in_string = false,
+						_ => {}
+					}
+					return false;
+				}
+				match c {
+					'"' => in_string = true,
+					'[' => depth += 1,
+					']' => depth -= 1,
+					_ => {}
+				}
+				depth == 0
+			});
+			let Some(close) = close else { break };
+			"#![".len() + close + 1
+		} else if rest.starts_with("//") {
+			rest.find('\n').map_or(rest.len(), |newline| newline + 1)
+		} else if rest.starts_with("/*") {
+			let Some(close) = rest.find("*/") else { break };
+			close + "*/".len()
+		} else {
+			break;
+		};
+
+		let is_comment = rest.starts_with("//") || rest.starts_with("/*");
+		let is_inner_doc = rest.starts_with("//!") || rest.starts_with("/*!");
+		offset += len;
+		if !is_comment || is_inner_doc {
+			end = offset;
+		}
+	}
+
+	end
+}
+
+const PROJECTS_DIR: &str = "packages";
+
+/// Each package is built in its own project so that the sources and
+/// manifests of different slides don't overwrite each other.
+fn package_project_dir(package_name: &str) -> String { format!("{PROJECTS_DIR}/{package_name}") }
+
+fn write_primary_file_request(
+	project_dir: &str,
+	crate_type: CrateType,
+	code: &str,
+) -> WriteFileRequest {
+	let path = format!("{project_dir}/{}", crate_type.primary_path());
+	WriteFileRequest { path, content: code.into() }
 }
 
-fn delete_previous_primary_file_request(crate_type: CrateType) -> DeleteFileRequest {
-	DeleteFileRequest { path: crate_type.other_path().to_owned() }
+fn delete_previous_primary_file_request(
+	project_dir: &str,
+	crate_type: CrateType,
+) -> DeleteFileRequest {
+	DeleteFileRequest { path: format!("{project_dir}/{}", crate_type.other_path()) }
 }
 
 #[derive(Debug)]
@@ -1015,6 +1605,11 @@ where
 	pub async fn miri(&self, request: MiriRequest) -> Result<WithOutput<MiriResponse>, MiriError> {
 		use miri_error::*;
 
+		ensure!(
+			request.channel == RustChannel::Nightly,
+			MiriRequiresNightlySnafu { channel: request.channel }
+		);
+
 		self.select_channel(request.channel)
 			.await
 			.context(CouldNotStartContainerSnafu)?
@@ -1029,6 +1624,11 @@ where
 	) -> Result<ActiveMiri, MiriError> {
 		use miri_error::*;
 
+		ensure!(
+			request.channel == RustChannel::Nightly,
+			MiriRequiresNightlySnafu { channel: request.channel }
+		);
+
 		self.select_channel(request.channel)
 			.await
 			.context(CouldNotStartContainerSnafu)?
@@ -1102,7 +1702,7 @@ where
 }
 
 impl Coordinator<DockerBackend> {
-	pub async fn new_docker() -> Self { Self::new(DockerBackend(())).await }
+	pub async fn new_docker() -> Self { Self::new(DockerBackend::default()).await }
 }
 
 #[derive(Debug)]
@@ -1111,6 +1711,11 @@ struct Container {
 	kill_child: Option<Command>,
 	modify_cargo_toml: ModifyCargoToml,
 	commander: Commander,
+	/// Where the worker's paths are relative to, as the compiler sees it
+	project_dir: String,
+	image: &'static str,
+	/// What the worker said it can do during the handshake
+	capabilities: Vec<Capability>,
 }
 
 impl Container {
@@ -1119,6 +1724,11 @@ impl Container {
 		token: CancellationToken,
 		backend: &impl Backend,
 	) -> Result<Self> {
+		// A stale cache only costs build time, so the worker starts anyway
+		if let Err(e) = backend.prepare_cargo_cache(channel).await {
+			warn!("Unable to trim the Cargo cache for {channel:?}: {e}");
+		}
+
 		let (mut child, kill_child, stdin, stdout) = backend.run_worker_in_background(channel)?;
 		let IoQueue { mut tasks, to_worker_tx, from_worker_rx } =
 			spawn_io_queue(stdin, stdout, token);
@@ -1145,10 +1755,60 @@ impl Container {
 		let commander =
 			Commander { to_worker_tx, to_demultiplexer_tx: command_tx, id: Default::default() };
 
+		let HelloResponse { build, capabilities, project_dir, .. } =
+			Self::handshake(&commander, channel).await?;
+		trace!(%build, ?capabilities, project_dir, "connected to worker");
+
 		let modify_cargo_toml =
 			ModifyCargoToml::new(commander.clone()).await.context(CouldNotLoadCargoTomlSnafu)?;
 
-		Ok(Container { task, kill_child, modify_cargo_toml, commander })
+		Ok(Container {
+			task,
+			kill_child,
+			modify_cargo_toml,
+			commander,
+			project_dir,
+			image: channel.to_container_name(),
+			capabilities,
+		})
+	}
+
+	/// Workers built before a feature was added still speak the same
+	/// protocol, so requests relying on it are refused up front.
+	fn require(&self, capability: Capability) -> Result<(), MissingCapabilityError> {
+		use missing_capability_error::*;
+
+		ensure!(
+			self.capabilities.contains(&capability),
+			MissingCapabilitySnafu { image: self.image, capability }
+		);
+		Ok(())
+	}
+
+	/// Workers that predate the handshake may not answer it at all, so a
+	/// missing response is treated the same as a version mismatch.
+	async fn handshake(commander: &Commander, channel: RustChannel) -> Result<HelloResponse> {
+		const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+		let image = channel.to_container_name();
+		let hello = HelloRequest { protocol_version: PROTOCOL_VERSION };
+
+		let hello = time::timeout(HANDSHAKE_TIMEOUT, commander.one(hello))
+			.await
+			.context(HandshakeTimedOutSnafu { image })?
+			.context(HandshakeSnafu { image })?;
+
+		ensure!(
+			hello.protocol_version == PROTOCOL_VERSION,
+			ProtocolVersionMismatchSnafu {
+				image,
+				build: hello.build,
+				worker: hello.protocol_version,
+				coordinator: PROTOCOL_VERSION,
+			}
+		);
+
+		Ok(hello)
 	}
 
 	async fn versions(&self) -> Result<ChannelVersions, ContainerVersionsError> {
@@ -1197,7 +1857,7 @@ impl Container {
 		token: CancellationToken,
 		cmd: ExecuteCommandRequest,
 	) -> Result<Option<String>, VersionError> {
-		let v = self.spawn_cargo_task(token.clone(), cmd).await?;
+		let v: SpawnCargo = self.spawn_cargo_task(token.clone(), cmd).await?;
 		let SpawnCargo { task, stdin_tx, stdout_rx, stderr_rx, status_rx } = v;
 
 		drop(stdin_tx);
@@ -1221,7 +1881,7 @@ impl Container {
 	) -> Result<WithOutput<ExecuteResponse>, ExecuteError> {
 		let token = Default::default();
 
-		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx } =
+		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx, .. } =
 			self.begin_execute(token, request).await?;
 
 		drop(stdin_tx);
@@ -1238,10 +1898,13 @@ impl Container {
 	) -> Result<ActiveExecution, ExecuteError> {
 		use execute_error::*;
 
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
 		let execute_cargo = request.execute_cargo_request();
 
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
+
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
 		let modify_cargo_toml = self.modify_cargo_toml.modify_for(&request);
@@ -1257,18 +1920,15 @@ impl Container {
 			self.spawn_cargo_task(token, execute_cargo).await.context(CouldNotStartCargoSnafu)?;
 
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
+			let ExecuteCommandResponse { success, exit_detail, statistics } =
 				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
-			Ok(ExecuteResponse { success, exit_detail })
+			let statistics = statistics.map(Into::into);
+			Ok(ExecuteResponse { success, exit_detail, statistics })
 		}
 		.boxed();
 
-		let status_rx = tokio_stream::wrappers::ReceiverStream::new(status_rx)
-			.map(|s| {
-				let CommandStatistics { total_time_secs, resident_set_size_bytes } = s;
-				ExecuteStatus { resident_set_size_bytes, total_time_secs }
-			})
-			.boxed();
+		let status_rx =
+			tokio_stream::wrappers::ReceiverStream::new(status_rx).map(ExecuteStatus::from).boxed();
 
 		Ok(ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx })
 	}
@@ -1293,22 +1953,42 @@ impl Container {
 	) -> Result<ActiveCompilation, CompileError> {
 		use compile_error::*;
 
+		if request.target == CompileTarget::WasmTest {
+			self.require(Capability::WasmTest)?;
+		}
+
 		let output_path: &str = "compilation";
 
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
+		let write_prelude = request.write_prelude_request();
 		let execute_cargo = request.execute_cargo_request(output_path);
-		let read_output = ReadFileRequest { path: output_path.to_owned() };
+		let read_output = request.read_output_request(output_path);
+		let is_package = request.target == CompileTarget::Wasm;
+		// Files of an earlier build must not end up in this package
+		let remove_package = is_package.then(|| request.remove_package_request());
+
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
 
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
+		let write_prelude = OptionFuture::from(write_prelude.map(|w| self.commander.one(w)));
+		let remove_package = OptionFuture::from(remove_package.map(|r| self.commander.one(r)));
 		let modify_cargo_toml = self.modify_cargo_toml.modify_for(&request);
 
-		let (delete_previous_main, write_main, modify_cargo_toml) =
-			join!(delete_previous_main, write_main, modify_cargo_toml);
+		let (delete_previous_main, write_main, write_prelude, remove_package, modify_cargo_toml) = join!(
+			delete_previous_main,
+			write_main,
+			write_prelude,
+			remove_package,
+			modify_cargo_toml
+		);
 
 		delete_previous_main.context(CouldNotDeletePreviousCodeSnafu)?;
 		write_main.context(CouldNotWriteCodeSnafu)?;
+		write_prelude.transpose().context(CouldNotWritePreludeSnafu)?;
+		remove_package.transpose().context(CouldNotRemovePackageSnafu)?;
 		modify_cargo_toml.context(CouldNotModifyCargoTomlSnafu)?;
 
 		let SpawnCargo { task, stdin_tx, stdout_rx, stderr_rx, status_rx } =
@@ -1318,11 +1998,21 @@ impl Container {
 		drop(status_rx);
 
 		let commander = self.commander.clone();
+		let project_dir = self.project_dir.clone();
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
+			let ExecuteCommandResponse { success, exit_detail, .. } =
 				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
 
-			let code = if success {
+			// Failing tests are reported the same way as passing ones
+			let is_test = request.target == CompileTarget::WasmTest;
+
+			let code = if success && is_package {
+				let ReadFilesResponse(files) = commander
+					.one(request.read_package_request())
+					.await
+					.context(CouldNotReadCodeSnafu)?;
+				CompiledCode::Package(request.package_files(files))
+			} else if success || is_test {
 				let file: ReadFileResponse =
 					commander.one(read_output).await.context(CouldNotReadCodeSnafu)?;
 				CompiledCode::from_bytes(file.0)
@@ -1330,10 +2020,106 @@ impl Container {
 				CompiledCode::CodeStr(String::new())
 			};
 
+			let tests = match (&code, is_test) {
+				(CompiledCode::CodeStr(output), true) => parse_test_results(output),
+				_ => Vec::new(),
+			};
+
+			let wasm_summary = match (&code, request.target) {
+				(CompiledCode::CodeStr(wat), CompileTarget::WasmText(_)) => {
+					Some(asm_cleanup::summarize_wat(wat))
+				}
+				_ => None,
+			};
+
+			let size_report = if success && request.target == CompileTarget::Wasm {
+				let stat_module = commander.one(request.stat_module_request(output_path));
+				let read_offsets = commander.one(request.read_module_offsets_request(output_path));
+				let (module, offsets) = join!(stat_module, read_offsets);
+
+				let StatResponse(module) = module.context(CouldNotReadSizeReportSnafu)?;
+				let ReadFileResponse(offsets) = offsets.context(CouldNotReadSizeReportSnafu)?;
+
+				Some(WasmSizeReport {
+					total_bytes: module.map_or(0, |m| m.size_bytes),
+					functions: asm_cleanup::function_sizes(&String::from_utf8_lossy(&offsets)),
+				})
+			} else {
+				None
+			};
+
+			let line_map = if success && request.target == CompileTarget::Wasm && request.debug_info
+			{
+				let read_line_table = commander.one(request.read_line_table_request(output_path));
+				let read_sections = commander.one(request.read_sections_request(output_path));
+				let (line_table, sections) = join!(read_line_table, read_sections);
+
+				let ReadFileResponse(line_table) =
+					line_table.context(CouldNotReadLineTableSnafu)?;
+				let ReadFileResponse(sections) = sections.context(CouldNotReadLineTableSnafu)?;
+
+				let code_offset =
+					asm_cleanup::code_section_offset(&String::from_utf8_lossy(&sections));
+				let mut lines = asm_cleanup::line_mappings(
+					&String::from_utf8_lossy(&line_table),
+					request.crate_type.primary_path(),
+				);
+				for line in &mut lines {
+					line.address += code_offset.unwrap_or(0);
+				}
+
+				Some(WasmLineMap { lines })
+			} else {
+				None
+			};
+
+			let asm_lines = match (&code, request.assembly_pipeline()) {
+				(CompiledCode::CodeStr(asm), Some(pipeline)) if request.debug_info => {
+					let path = format!("{project_dir}/{}", request.source_path());
+					// The same function that `postprocess_result` extracts
+					let function = request.item.as_ref().and_then(|item| {
+						let asm = asm_cleanup::demangle_asm(asm);
+						asm_cleanup::annotate_asm_function(&asm, item, &path)
+					});
+					// Then the same steps as `postprocess_result` runs on the code
+					let (lines, pipeline) = match function {
+						Some(lines) => (lines, AsmPipeline { demangle: false, ..pipeline }),
+						None if pipeline.filter => {
+							(asm_cleanup::filter_annotated_asm(asm, &path), pipeline)
+						}
+						None => (asm_cleanup::annotate_asm(asm, &path), pipeline),
+					};
+					Some(asm_cleanup::process_annotated_asm(lines, pipeline))
+				}
+				_ => None,
+			};
+
+			let asm_stats = match (&code, request.target) {
+				(CompiledCode::CodeStr(asm), CompileTarget::Assembly(..)) if success => {
+					// Like the code, the whole crate when the item isn't found
+					let stats = match &request.item {
+						Some(item) => asm_cleanup::asm_function_stats(asm, item),
+						None => vec![],
+					};
+					Some(if stats.is_empty() { asm_cleanup::asm_stats(asm) } else { stats })
+				}
+				_ => None,
+			};
+
 			// TODO: This is synchronous...
 			let code = request.postprocess_result(code);
 
-			Ok(CompileResponse { success, exit_detail, code })
+			Ok(CompileResponse {
+				success,
+				exit_detail,
+				code,
+				tests,
+				wasm_summary,
+				size_report,
+				line_map,
+				asm_lines,
+				asm_stats,
+			})
 		}
 		.boxed();
 
@@ -1359,10 +2145,13 @@ impl Container {
 	) -> Result<ActiveFormatting, FormatError> {
 		use format_error::*;
 
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
 		let execute_cargo = request.execute_cargo_request();
-		let read_output = ReadFileRequest { path: request.crate_type.primary_path().to_owned() };
+		let read_output = request.read_main_request();
+
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
 
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
@@ -1383,7 +2172,7 @@ impl Container {
 
 		let commander = self.commander.clone();
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
+			let ExecuteCommandResponse { success, exit_detail, .. } =
 				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
 
 			let file = commander.one(read_output).await.context(CouldNotReadCodeSnafu)?;
@@ -1414,10 +2203,13 @@ impl Container {
 	) -> Result<ActiveClippy, ClippyError> {
 		use clippy_error::*;
 
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
 		let execute_cargo = request.execute_cargo_request();
 
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
+
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
 		let modify_cargo_toml = self.modify_cargo_toml.modify_for(&request);
@@ -1436,7 +2228,7 @@ impl Container {
 		drop(status_rx);
 
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
+			let ExecuteCommandResponse { success, exit_detail, .. } =
 				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
 
 			Ok(ClippyResponse { success, exit_detail })
@@ -1461,10 +2253,17 @@ impl Container {
 	) -> Result<ActiveMiri, MiriError> {
 		use miri_error::*;
 
+		if request.flags != MiriFlags::default() {
+			self.require(Capability::MiriFlags)?;
+		}
+
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
 		let execute_cargo = request.execute_cargo_request();
 
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
+
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
 		let modify_cargo_toml = self.modify_cargo_toml.modify_for(&request);
@@ -1483,7 +2282,7 @@ impl Container {
 		drop(status_rx);
 
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
+			let ExecuteCommandResponse { success, exit_detail, .. } =
 				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
 
 			Ok(MiriResponse { success, exit_detail })
@@ -1512,10 +2311,13 @@ impl Container {
 	) -> Result<ActiveMacroExpansion, MacroExpansionError> {
 		use macro_expansion_error::*;
 
+		let create_project = request.create_project_request();
 		let delete_previous_main = request.delete_previous_main_request();
 		let write_main = request.write_main_request();
 		let execute_cargo = request.execute_cargo_request();
 
+		self.commander.one(create_project).await.context(CouldNotCreateProjectSnafu)?;
+
 		let delete_previous_main = self.commander.one(delete_previous_main);
 		let write_main = self.commander.one(write_main);
 		let modify_cargo_toml = self.modify_cargo_toml.modify_for(&request);
@@ -1533,9 +2335,25 @@ impl Container {
 		drop(stdin_tx);
 		drop(status_rx);
 
+		// The expansion has to be complete before an item can be picked
+		// out of it, so it's sent as one chunk at the end
+		let (stdout_rx, scope_to_item) = match request.item {
+			Some(item) => {
+				let (item_tx, item_rx) = mpsc::channel(1);
+				let scope_to_item = async move {
+					let expanded: String = ReceiverStream::new(stdout_rx).collect().await;
+					let expanded = asm_cleanup::extract_item(&expanded, &item).unwrap_or(expanded);
+					item_tx.send(expanded).await.ok(/* Receiver gone, that's OK */);
+				};
+				(item_rx, Some(scope_to_item))
+			}
+			None => (stdout_rx, None),
+		};
+
 		let task = async move {
-			let ExecuteCommandResponse { success, exit_detail } =
-				task.await.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
+			let (response, _) = join!(task, OptionFuture::from(scope_to_item));
+			let ExecuteCommandResponse { success, exit_detail, .. } =
+				response.context(CargoTaskPanickedSnafu)?.context(CargoFailedSnafu)?;
 
 			Ok(MacroExpansionResponse { success, exit_detail })
 		}
@@ -1544,13 +2362,21 @@ impl Container {
 		Ok(ActiveMacroExpansion { task, stdout_rx, stderr_rx })
 	}
 
-	async fn spawn_cargo_task(
+	/// The output arrives as [`OutputChunk`]s or, with invalid UTF-8
+	/// replaced, as text.
+	async fn spawn_cargo_task<O>(
 		&self,
 		token: CancellationToken,
 		execute_cargo: ExecuteCommandRequest,
-	) -> Result<SpawnCargo, SpawnCargoError> {
+	) -> Result<SpawnCargo<O>, SpawnCargoError>
+	where
+		O: From<OutputChunk> + Send + 'static,
+	{
 		use spawn_cargo_error::*;
 
+		let stdin_bytes = self.require(Capability::StdinBytes).is_ok();
+		let image = self.image;
+
 		let (stdin_tx, mut stdin_rx) = mpsc::channel(8);
 		let (stdout_tx, stdout_rx) = mpsc::channel(8);
 		let (stderr_tx, stderr_rx) = mpsc::channel(8);
@@ -1576,7 +2402,14 @@ impl Container {
 
 						stdin = stdin_rx.recv(), if stdin_open => {
 							let msg = match stdin {
+								Some(stdin) if stdin_bytes => {
+									CoordinatorMessage::StdinBytes(stdin)
+								}
+
+								// Text is all that older workers understand
 								Some(stdin) => {
+									let stdin = String::from_utf8(stdin)
+										.context(StdinNotTextSnafu { image })?;
 									CoordinatorMessage::StdinPacket(stdin)
 								}
 
@@ -1599,10 +2432,22 @@ impl Container {
 								}
 
 								WorkerMessage::StdoutPacket(packet) => {
+									let packet = OutputChunk::Text(packet).into();
 									stdout_tx.send(packet).await.ok(/* Receiver gone, that's OK */);
 								}
 
 								WorkerMessage::StderrPacket(packet) => {
+									let packet = OutputChunk::Text(packet).into();
+									stderr_tx.send(packet).await.ok(/* Receiver gone, that's OK */);
+								}
+
+								WorkerMessage::StdoutBytes(packet) => {
+									let packet = OutputChunk::Bytes(packet).into();
+									stdout_tx.send(packet).await.ok(/* Receiver gone, that's OK */);
+								}
+
+								WorkerMessage::StderrBytes(packet) => {
+									let packet = OutputChunk::Bytes(packet).into();
 									stderr_tx.send(packet).await.ok(/* Receiver gone, that's OK */);
 								}
 
@@ -1610,9 +2455,6 @@ impl Container {
 									status_tx.send(stats).await.ok(/* Receiver gone, that's OK */);
 								}
 
-								WorkerMessage::Error(e) =>
-									return Err(SerializedError2::adapt(e)).context(WorkerSnafu),
-
 								WorkerMessage::Error2(e) =>
 									return Err(e).context(WorkerSnafu),
 
@@ -1634,7 +2476,7 @@ impl Container {
 	}
 
 	async fn shutdown(self) -> Result<()> {
-		let Self { task, kill_child, modify_cargo_toml, commander } = self;
+		let Self { task, kill_child, modify_cargo_toml, commander, .. } = self;
 		drop(commander);
 		drop(modify_cargo_toml);
 
@@ -1654,11 +2496,13 @@ impl Container {
 	}
 }
 
+/// Output that is not valid UTF-8 arrives as [`OutputChunk::Bytes`];
+/// convert the chunks into a `String` if only the text is of interest.
 pub struct ActiveExecution {
 	pub task: BoxFuture<'static, Result<ExecuteResponse, ExecuteError>>,
-	pub stdin_tx: mpsc::Sender<String>,
-	pub stdout_rx: mpsc::Receiver<String>,
-	pub stderr_rx: mpsc::Receiver<String>,
+	pub stdin_tx: mpsc::Sender<Vec<u8>>,
+	pub stdout_rx: mpsc::Receiver<OutputChunk>,
+	pub stderr_rx: mpsc::Receiver<OutputChunk>,
 	pub status_rx: BoxStream<'static, ExecuteStatus>,
 }
 
@@ -1679,6 +2523,9 @@ pub enum ExecuteError {
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
 	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
@@ -1720,7 +2567,13 @@ pub enum CompileError {
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
-	#[snafu(display("Could not modify Cargo.toml"))]
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
+	#[snafu(display("Could not write the prelude"))]
+	CouldNotWritePrelude { source: CommanderError },
+
+	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
 	#[snafu(display("Could not delete previous source code"))]
@@ -1729,6 +2582,9 @@ pub enum CompileError {
 	#[snafu(display("Could not write source code"))]
 	CouldNotWriteCode { source: CommanderError },
 
+	#[snafu(display("Could not remove the previous package"))]
+	CouldNotRemovePackage { source: CommanderError },
+
 	#[snafu(display("Could not start Cargo task"))]
 	CouldNotStartCargo { source: SpawnCargoError },
 
@@ -1741,11 +2597,20 @@ pub enum CompileError {
 	#[snafu(display("Could not read the compilation output"))]
 	CouldNotReadCode { source: CommanderError },
 
+	#[snafu(display("Could not read the size of the compiled module"))]
+	CouldNotReadSizeReport { source: CommanderError },
+
+	#[snafu(display("Could not read the line table of the compiled module"))]
+	CouldNotReadLineTable { source: CommanderError },
+
 	#[snafu(display("The compilation output was not UTF-8"))]
 	CodeNotUtf8 { source: std::string::FromUtf8Error },
 
 	#[snafu(display("Cannot compile requested language"))]
 	UnsupportedLanguage { language: Language },
+
+	#[snafu(transparent)]
+	MissingCapability { source: MissingCapabilityError },
 }
 
 pub struct ActiveFormatting {
@@ -1770,6 +2635,9 @@ pub enum FormatError {
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
 	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
@@ -1817,6 +2685,9 @@ pub enum ClippyError {
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
 	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
@@ -1855,9 +2726,15 @@ impl fmt::Debug for ActiveMiri {
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum MiriError {
+	#[snafu(display("Miri is only available on the nightly channel, not {channel:?}"))]
+	MiriRequiresNightly { channel: RustChannel },
+
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
 	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
@@ -1875,6 +2752,9 @@ pub enum MiriError {
 
 	#[snafu(display("Cargo task failed"))]
 	CargoFailed { source: SpawnCargoError },
+
+	#[snafu(transparent)]
+	MissingCapability { source: MissingCapabilityError },
 }
 
 pub struct ActiveMacroExpansion {
@@ -1899,6 +2779,9 @@ pub enum MacroExpansionError {
 	#[snafu(display("Could not start the container"))]
 	CouldNotStartContainer { source: Error },
 
+	#[snafu(display("Could not create the project for the package"))]
+	CouldNotCreateProject { source: CommanderError },
+
 	#[snafu(display("Could not modify Cargo.toml"))]
 	CouldNotModifyCargoToml { source: ModifyCargoTomlError },
 
@@ -1918,11 +2801,11 @@ pub enum MacroExpansionError {
 	CargoFailed { source: SpawnCargoError },
 }
 
-struct SpawnCargo {
+struct SpawnCargo<O = String> {
 	task: JoinHandle<Result<ExecuteCommandResponse, SpawnCargoError>>,
-	stdin_tx: mpsc::Sender<String>,
-	stdout_rx: mpsc::Receiver<String>,
-	stderr_rx: mpsc::Receiver<String>,
+	stdin_tx: mpsc::Sender<Vec<u8>>,
+	stdout_rx: mpsc::Receiver<O>,
+	stderr_rx: mpsc::Receiver<O>,
 	status_rx: mpsc::Receiver<CommandStatistics>,
 }
 
@@ -1944,6 +2827,11 @@ pub enum SpawnCargoError {
 	#[snafu(display("Unable to send stdin message"))]
 	Stdin { source: MultiplexedSenderError },
 
+	#[snafu(display(
+		"The worker in the {image} image only accepts UTF-8 input; rebuild the image with `compiler/build.sh`"
+	))]
+	StdinNotText { source: std::string::FromUtf8Error, image: &'static str },
+
 	#[snafu(display("Unable to send kill message"))]
 	Kill { source: MultiplexedSenderError },
 }
@@ -1957,6 +2845,10 @@ struct Commander {
 
 trait CargoTomlModifier {
 	fn modify_cargo_toml(&self, cargo_toml: toml::Value) -> toml::Value;
+
+	/// The project whose `Cargo.toml` should be written, relative to
+	/// the base project.
+	fn project_dir(&self) -> String;
 }
 
 #[derive(Debug)]
@@ -1979,7 +2871,9 @@ impl ModifyCargoToml {
 	) -> Result<(), ModifyCargoTomlError> {
 		let cargo_toml = self.cargo_toml.clone();
 		let cargo_toml = request.modify_cargo_toml(cargo_toml);
-		Self::write(&self.commander, cargo_toml).await
+
+		let path = format!("{}/{}", request.project_dir(), Self::PATH);
+		Self::write(&self.commander, path, cargo_toml).await
 	}
 
 	async fn read(commander: &Commander) -> Result<toml::Value, ModifyCargoTomlError> {
@@ -1997,6 +2891,7 @@ impl ModifyCargoToml {
 
 	async fn write(
 		commander: &Commander,
+		path: String,
 		cargo_toml: toml::Value,
 	) -> Result<(), ModifyCargoTomlError> {
 		use modify_cargo_toml_error::*;
@@ -2004,7 +2899,6 @@ impl ModifyCargoToml {
 		let cargo_toml = toml::to_string(&cargo_toml)?;
 		let content = cargo_toml.into_bytes();
 
-		let path = Self::PATH.to_owned();
 		commander.one(WriteFileRequest { path, content }).await.context(CouldNotWriteSnafu)?;
 
 		Ok(())
@@ -2052,6 +2946,16 @@ impl MultiplexedSender {
 	}
 }
 
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+#[snafu(display(
+	"The worker in the {image} image does not support {capability:?}; rebuild the image with `compiler/build.sh`"
+))]
+pub struct MissingCapabilityError {
+	image: &'static str,
+	capability: Capability,
+}
+
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 #[snafu(display("Could not send a message to the worker"))]
@@ -2248,6 +3152,15 @@ pub trait Backend {
 	}
 
 	fn prepare_worker_command(&self, channel: RustChannel) -> (Command, Option<Command>);
+
+	/// Called before each worker is started so that any persistent
+	/// build cache can be checked and trimmed.
+	fn prepare_cargo_cache(
+		&self,
+		_channel: RustChannel,
+	) -> BoxFuture<'_, Result<(), CargoCacheError>> {
+		async { Ok(()) }.boxed()
+	}
 }
 
 impl<B> Backend for &B
@@ -2257,6 +3170,13 @@ where
 	fn prepare_worker_command(&self, channel: RustChannel) -> (Command, Option<Command>) {
 		B::prepare_worker_command(self, channel)
 	}
+
+	fn prepare_cargo_cache(
+		&self,
+		channel: RustChannel,
+	) -> BoxFuture<'_, Result<(), CargoCacheError>> {
+		B::prepare_cargo_cache(self, channel)
+	}
 }
 
 macro_rules! docker_command {
@@ -2314,9 +3234,124 @@ static DOCKER_BACKEND_START: Lazy<u64> = Lazy::new(|| {
 
 static DOCKER_BACKEND_ID: AtomicU64 = AtomicU64::new(0);
 
-pub struct DockerBackend(());
+/// Keeps the worker's `target/` and Cargo registry in named Docker
+/// volumes so that build artifacts survive container restarts.
+///
+/// A new volume is seeded by Docker with the contents of the image,
+/// so the pre-built dependencies are still available the first time
+/// a volume is used. Removing the volumes resets the cache to what
+/// the image contains.
+#[derive(Debug, Clone)]
+pub struct CargoCache {
+	/// Prefix of the volume names; one pair of volumes is created per
+	/// channel.
+	pub volume_prefix: String,
+	/// When the volumes of a channel grow beyond this size they are
+	/// removed before the next worker for that channel starts.
+	pub max_size_bytes: Option<u64>,
+}
+
+impl CargoCache {
+	pub const DEFAULT_VOLUME_PREFIX: &'static str = "asa-present-cargo-cache";
+	const REGISTRY_DIR: &'static str = "/playground/.cargo/registry";
+	const TARGET_DIR: &'static str = "/playground/target";
+
+	fn target_volume(&self, channel: RustChannel) -> String {
+		format!("{}-{}-target", self.volume_prefix, channel.to_container_name())
+	}
+
+	fn registry_volume(&self, channel: RustChannel) -> String {
+		format!("{}-{}-registry", self.volume_prefix, channel.to_container_name())
+	}
+
+	fn mounts(&self, channel: RustChannel) -> [String; 2] {
+		[
+			(self.target_volume(channel), Self::TARGET_DIR),
+			(self.registry_volume(channel), Self::REGISTRY_DIR),
+		]
+		.map(|(volume, dir)| format!("type=volume,source={volume},target={dir}"))
+	}
+
+	/// Adds up the sizes `du --summarize --bytes` printed for the
+	/// directories.
+	fn parse_usage(du_output: &str) -> u64 {
+		du_output
+			.lines()
+			.filter_map(|line| line.split_whitespace().next()?.parse::<u64>().ok())
+			.sum()
+	}
+
+	fn needs_trim(&self, usage: u64) -> bool {
+		self.max_size_bytes.is_some_and(|max_size_bytes| usage > max_size_bytes)
+	}
+}
+
+impl Default for CargoCache {
+	fn default() -> Self {
+		Self { volume_prefix: Self::DEFAULT_VOLUME_PREFIX.to_owned(), max_size_bytes: None }
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct DockerBackend {
+	cargo_cache: Option<CargoCache>,
+}
 
 impl DockerBackend {
+	pub fn with_cargo_cache(cargo_cache: CargoCache) -> Self {
+		Self { cargo_cache: Some(cargo_cache) }
+	}
+
+	/// The number of bytes used by the cache volumes of `channel`.
+	pub async fn cargo_cache_usage(&self, channel: RustChannel) -> Result<u64, CargoCacheError> {
+		use cargo_cache_error::*;
+
+		let Some(cargo_cache) = &self.cargo_cache else { return Ok(0) };
+
+		let mut command = basic_secure_docker_command();
+		command.arg("--rm");
+		for mount in cargo_cache.mounts(channel) {
+			command.args(["--mount", &mount]);
+		}
+		command.args(["-e", "PLAYGROUND_ORCHESTRATOR=1"]).arg(channel.to_container_name());
+		command.args([
+			"du",
+			"--summarize",
+			"--bytes",
+			CargoCache::TARGET_DIR,
+			CargoCache::REGISTRY_DIR,
+		]);
+
+		let output = command.output().await.context(UnableToMeasureSnafu)?;
+		ensure!(
+			output.status.success(),
+			MeasureFailedSnafu { stderr: String::from_utf8_lossy(&output.stderr) }
+		);
+
+		Ok(CargoCache::parse_usage(&String::from_utf8_lossy(&output.stdout)))
+	}
+
+	/// Removes the cache volumes of `channel`. The next worker will
+	/// start from the artifacts baked into the image.
+	pub async fn clean_cargo_cache(&self, channel: RustChannel) -> Result<(), CargoCacheError> {
+		use cargo_cache_error::*;
+
+		let Some(cargo_cache) = &self.cargo_cache else { return Ok(()) };
+
+		let output = docker_command!("volume", "rm", "--force")
+			.arg(cargo_cache.target_volume(channel))
+			.arg(cargo_cache.registry_volume(channel))
+			.output()
+			.await
+			.context(UnableToCleanSnafu)?;
+		ensure!(
+			output.status.success(),
+			CleanFailedSnafu { stderr: String::from_utf8_lossy(&output.stderr) }
+		);
+
+		Ok(())
+	}
+
 	fn next_name(&self) -> String {
 		let start = *DOCKER_BACKEND_START;
 		let id = DOCKER_BACKEND_ID.fetch_add(1, Ordering::SeqCst);
@@ -2334,16 +3369,59 @@ impl Backend for DockerBackend {
 			.arg("-i")
 			.args(["-a", "stdin", "-a", "stdout", "-a", "stderr"])
 			.args(["-e", "PLAYGROUND_ORCHESTRATOR=1"])
-			.arg("--rm")
-			.arg(channel.to_container_name())
-			.arg("worker")
-			.arg("/playground");
+			.arg("--rm");
+
+		if let Some(cargo_cache) = &self.cargo_cache {
+			for mount in cargo_cache.mounts(channel) {
+				command.args(["--mount", &mount]);
+			}
+		}
+
+		command.arg(channel.to_container_name()).arg("worker").arg("/playground");
 
 		let mut kill = Command::new("docker");
 		kill.arg("kill").args(["--signal", "KILL"]).arg(name);
 
 		(command, Some(kill))
 	}
+
+	fn prepare_cargo_cache(
+		&self,
+		channel: RustChannel,
+	) -> BoxFuture<'_, Result<(), CargoCacheError>> {
+		async move {
+			let Some(cargo_cache) =
+				self.cargo_cache.as_ref().filter(|c| c.max_size_bytes.is_some())
+			else {
+				return Ok(());
+			};
+
+			let usage = self.cargo_cache_usage(channel).await?;
+			if cargo_cache.needs_trim(usage) {
+				warn!("Cargo cache for {channel:?} is {usage} bytes; removing it");
+				self.clean_cargo_cache(channel).await?;
+			}
+
+			Ok(())
+		}
+		.boxed()
+	}
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum CargoCacheError {
+	#[snafu(display("Unable to start the process measuring the Cargo cache"))]
+	UnableToMeasure { source: std::io::Error },
+
+	#[snafu(display("Measuring the Cargo cache failed: {stderr}"))]
+	MeasureFailed { stderr: String },
+
+	#[snafu(display("Unable to start the process removing the Cargo cache"))]
+	UnableToClean { source: std::io::Error },
+
+	#[snafu(display("Removing the Cargo cache failed: {stderr}"))]
+	CleanFailed { stderr: String },
 }
 
 impl RustChannel {
@@ -2401,6 +3479,26 @@ pub enum Error {
 
 	#[snafu(display("Unable to load original Cargo.toml"))]
 	CouldNotLoadCargoToml { source: ModifyCargoTomlError },
+
+	#[snafu(display(
+		"The worker in the {image} image did not answer the handshake; the image is probably out of date, rebuild it with `compiler/build.sh`"
+	))]
+	HandshakeTimedOut { source: tokio::time::error::Elapsed, image: &'static str },
+
+	#[snafu(display(
+		"Unable to complete the handshake with the worker in the {image} image; the image may be out of date, rebuild it with `compiler/build.sh`"
+	))]
+	Handshake { source: CommanderError, image: &'static str },
+
+	#[snafu(display(
+		"The worker in the {image} image (built from {build}) speaks protocol version {worker} but version {coordinator} is required; rebuild the image with `compiler/build.sh`"
+	))]
+	ProtocolVersionMismatch {
+		image: &'static str,
+		build: WorkerBuildInfo,
+		worker: u32,
+		coordinator: u32,
+	},
 }
 
 struct IoQueue {
@@ -2630,7 +3728,7 @@ mod tests {
 		Ok(())
 	}
 
-	const ARBITRARY_EXECUTE_REQUEST: ExecuteRequest = ExecuteRequest {
+	static ARBITRARY_EXECUTE_REQUEST: Lazy<ExecuteRequest> = Lazy::new(|| ExecuteRequest {
 		channel: RustChannel::Stable,
 		mode: Mode::Debug,
 		edition: RustEdition::Rust2021,
@@ -2638,12 +3736,15 @@ mod tests {
 		tests: false,
 		backtrace: false,
 		code: String::new(),
-	};
+		package_name: "playground".into(),
+		statistics_interval: None,
+		output_limit: None,
+	});
 
 	fn new_execute_request() -> ExecuteRequest {
 		ExecuteRequest {
 			code: r#"fn main() { println!("Hello, coordinator!"); }"#.into(),
-			..ARBITRARY_EXECUTE_REQUEST
+			..ARBITRARY_EXECUTE_REQUEST.clone()
 		}
 	}
 
@@ -2712,7 +3813,7 @@ mod tests {
 						edition,
 						crate_type: CrateType::Library(LibraryType::Lib),
 						channel: RustChannel::Nightly, // To allow 2024 while it is unstable
-						..ARBITRARY_EXECUTE_REQUEST
+						..ARBITRARY_EXECUTE_REQUEST.clone()
 					};
 					let response = coordinator.execute(request).await.unwrap();
 
@@ -2828,14 +3929,14 @@ mod tests {
                 }
             "#
 			.into(),
-			..ARBITRARY_EXECUTE_REQUEST
+			..ARBITRARY_EXECUTE_REQUEST.clone()
 		};
 
 		let token = Default::default();
-		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx: _status_rx } =
+		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx: _status_rx, .. } =
 			coordinator.begin_execute(token, request).await.unwrap();
 
-		stdin_tx.send("this is stdin\n".into()).await.unwrap();
+		stdin_tx.send(b"this is stdin\n".to_vec()).await.unwrap();
 		// Purposefully not dropping stdin_tx / status_rx early --
 		// real users might forget.
 
@@ -2869,18 +3970,18 @@ mod tests {
                 }
             "#
 			.into(),
-			..ARBITRARY_EXECUTE_REQUEST
+			..ARBITRARY_EXECUTE_REQUEST.clone()
 		};
 
 		let token = Default::default();
-		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx: _ } =
+		let ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx: _, .. } =
 			coordinator.begin_execute(token, request).await.unwrap();
 
 		for i in 0..3 {
-			stdin_tx.send(format!("line {i}\n")).await.unwrap();
+			stdin_tx.send(format!("line {i}\n").into_bytes()).await.unwrap();
 		}
 
-		stdin_tx.send("no newline".into()).await.unwrap();
+		stdin_tx.send(b"no newline".to_vec()).await.unwrap();
 		drop(stdin_tx); // Close the stdin handle
 
 		let WithOutput { response, stdout, stderr } =
@@ -2913,15 +4014,15 @@ mod tests {
                 }
             "#
 			.into(),
-			..ARBITRARY_EXECUTE_REQUEST
+			..ARBITRARY_EXECUTE_REQUEST.clone()
 		};
 
 		let token = CancellationToken::new();
-		let ActiveExecution { task, stdin_tx: _, stdout_rx, stderr_rx, status_rx: _ } =
+		let ActiveExecution { task, stdin_tx: _, stdout_rx, stderr_rx, status_rx: _, .. } =
 			coordinator.begin_execute(token.clone(), request).await.unwrap();
 
-		let stdout_rx = ReceiverStream::new(stdout_rx);
-		let stderr_rx = ReceiverStream::new(stderr_rx);
+		let stdout_rx = ReceiverStream::new(stdout_rx).map(String::from);
+		let stderr_rx = ReceiverStream::new(stderr_rx).map(String::from);
 
 		// We (a) want to wait for some output before we try to
 		// kill the process and (b) need to keep pumping stdout /
@@ -2962,11 +4063,11 @@ mod tests {
                 }
             "#
 			.into(),
-			..ARBITRARY_EXECUTE_REQUEST
+			..ARBITRARY_EXECUTE_REQUEST.clone()
 		};
 
 		let token = CancellationToken::new();
-		let ActiveExecution { task, stdin_tx: _, stdout_rx, stderr_rx, status_rx } =
+		let ActiveExecution { task, stdin_tx: _, stdout_rx, stderr_rx, status_rx, .. } =
 			coordinator.begin_execute(token.clone(), request).await.unwrap();
 
 		let statuses = status_rx.collect::<Vec<_>>();
@@ -2989,25 +4090,80 @@ mod tests {
 		Ok(())
 	}
 
+	// 10 000 bytes of stdout; Cargo's own output stays well below the limit
+	const PRINTS_TOO_MUCH: &str = r#"fn main() { for i in 0..1000 { println!("line {i:04}"); } }"#;
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn execute_output_limit_truncate() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let request = ExecuteRequest {
+			code: PRINTS_TOO_MUCH.into(),
+			output_limit: Some(OutputLimit { bytes: 1000, mode: OutputLimitMode::Truncate }),
+			..ARBITRARY_EXECUTE_REQUEST.clone()
+		};
+
+		let response = coordinator.execute(request).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		assert_starts_with!(response.stdout, "line 0000\n");
+		assert_ends_with!(response.stdout, "line 0999\n");
+		assert_contains!(response.stdout, "[... 9000 bytes of output omitted ...]");
+		assert_not_contains!(response.stdout, "line 0500");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn execute_output_limit_abort() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let request = ExecuteRequest {
+			code: PRINTS_TOO_MUCH.into(),
+			output_limit: Some(OutputLimit { bytes: 1000, mode: OutputLimitMode::Abort }),
+			..ARBITRARY_EXECUTE_REQUEST.clone()
+		};
+
+		let error = coordinator.execute(request).with_timeout().await.unwrap_err();
+		let error = snafu::Report::from_error(error).to_string();
+
+		assert_contains!(error, "bytes of output, exiting");
+		assert_contains!(error, "ASA_OUTPUT_LIMIT_MODE=truncate");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
 	const HELLO_WORLD_CODE: &str = r#"fn main() { println!("Hello World!"); }"#;
 
-	const ARBITRARY_COMPILE_REQUEST: CompileRequest = CompileRequest {
+	static ARBITRARY_COMPILE_REQUEST: Lazy<CompileRequest> = Lazy::new(|| CompileRequest {
 		target: CompileTarget::Mir,
-		channel: RustChannel::Stable,
+		language: RustSpec::new(RustChannel::Stable, RustEdition::Rust2021).into(),
 		crate_type: CrateType::Binary,
 		mode: Mode::Release,
-		edition: RustEdition::Rust2021,
-		tests: false,
-		backtrace: false,
 		code: String::new(),
-	};
+		package_name: "playground".into(),
+		output_limit: None,
+		release_profile: Default::default(),
+		debug_info: false,
+		console_prelude: false,
+		item: None,
+		asm_pipeline: Default::default(),
+		assembly_architecture: Default::default(),
+	});
 
 	#[tokio::test]
 	#[snafu::report]
 	async fn compile_response() -> Result<()> {
 		let coordinator = new_coordinator().await;
 
-		let req = CompileRequest { code: HELLO_WORLD_CODE.into(), ..ARBITRARY_COMPILE_REQUEST };
+		let req =
+			CompileRequest { code: HELLO_WORLD_CODE.into(), ..ARBITRARY_COMPILE_REQUEST.clone() };
 
 		let response = coordinator.compile(req).with_timeout().await.unwrap();
 
@@ -3025,7 +4181,8 @@ mod tests {
 	async fn compile_streaming() -> Result<()> {
 		let coordinator = new_coordinator().await;
 
-		let req = CompileRequest { code: HELLO_WORLD_CODE.into(), ..ARBITRARY_COMPILE_REQUEST };
+		let req =
+			CompileRequest { code: HELLO_WORLD_CODE.into(), ..ARBITRARY_COMPILE_REQUEST.clone() };
 
 		let token = Default::default();
 		let ActiveCompilation { task, stdout_rx, stderr_rx } =
@@ -3050,10 +4207,10 @@ mod tests {
 			let coordinator = new_coordinator().await;
 
 			let req = CompileRequest {
-				edition,
+				// Nightly to allow 2024 while it is unstable
+				language: RustSpec::new(RustChannel::Nightly, edition).into(),
 				code: SUBTRACT_CODE.into(),
-				channel: RustChannel::Nightly, // To allow 2024 while it is unstable
-				..ARBITRARY_HIR_REQUEST
+				..ARBITRARY_HIR_REQUEST.clone()
 			};
 
 			let response = coordinator.compile(req).with_timeout().await.unwrap();
@@ -3061,7 +4218,10 @@ mod tests {
 			let prelude = format!("std::prelude::rust_{}", edition.to_str());
 
 			assert!(response.success, "stderr: {}", response.stderr);
-			assert_contains!(response.code, &prelude);
+			let CompiledCode::CodeStr(code) = &response.code else {
+				panic!("Expected HIR, got {:?}", response.code);
+			};
+			assert_contains!(code, &prelude);
 
 			coordinator.shutdown().await?;
 		}
@@ -3071,20 +4231,17 @@ mod tests {
 
 	const ADD_CODE: &str = r#"#[inline(never)] pub fn add(a: u8, b: u8) -> u8 { a + b }"#;
 
-	const ARBITRARY_ASSEMBLY_REQUEST: CompileRequest = CompileRequest {
+	static ARBITRARY_ASSEMBLY_REQUEST: Lazy<CompileRequest> = Lazy::new(|| CompileRequest {
 		target: CompileTarget::Assembly(
 			DEFAULT_ASSEMBLY_FLAVOR,
 			DEFAULT_ASSEMBLY_DEMANGLE,
 			DEFAULT_ASSEMBLY_PROCESS,
 		),
-		channel: RustChannel::Beta,
+		language: RustSpec::new(RustChannel::Beta, RustEdition::Rust2018).into(),
 		crate_type: CrateType::Library(LibraryType::Lib),
 		mode: Mode::Release,
-		edition: RustEdition::Rust2018,
-		tests: false,
-		backtrace: false,
-		code: String::new(),
-	};
+		..ARBITRARY_COMPILE_REQUEST.clone()
+	});
 
 	const DEFAULT_ASSEMBLY_FLAVOR: AssemblyFlavor = AssemblyFlavor::Intel;
 	const DEFAULT_ASSEMBLY_DEMANGLE: DemangleAssembly = DemangleAssembly::Demangle;
@@ -3095,7 +4252,7 @@ mod tests {
 	async fn compile_assembly() -> Result<()> {
 		let coordinator = new_coordinator().await;
 
-		let req = CompileRequest { code: ADD_CODE.into(), ..ARBITRARY_ASSEMBLY_REQUEST };
+		let req = CompileRequest { code: ADD_CODE.into(), ..ARBITRARY_ASSEMBLY_REQUEST.clone() };
 
 		let response = coordinator.compile(req).with_timeout().await.unwrap();
 
@@ -3105,7 +4262,197 @@ mod tests {
 		};
 
 		assert!(response.success, "stderr: {}", response.stderr);
-		assert_contains!(response.code, asm);
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected assembly, got {:?}", response.code);
+		};
+		assert_contains!(code, asm);
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_source_lines() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = CompileRequest {
+			code: format!("\n{ADD_CODE}"),
+			debug_info: true,
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+
+		let asm_lines = response.asm_lines.as_deref().unwrap_or_default();
+		assert!(asm_lines.iter().any(|l| l.source_line == Some(2)), "{asm_lines:?}");
+		assert!(asm_lines.iter().all(|l| !l.text.contains(".loc")), "{asm_lines:?}");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_stats() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = CompileRequest {
+			code: "#[no_mangle] pub fn get(v: &[u32], i: usize) -> u32 { v[i] }".into(),
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+
+		let stats = response.asm_stats.as_deref().unwrap_or_default();
+		let get = stats.iter().find(|s| s.name == "get");
+		assert!(get.is_some_and(|get| get.bounds_checks == 1), "{stats:?}");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_source_lines_match_the_code() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = CompileRequest {
+			code: ADD_CODE.into(),
+			debug_info: true,
+			asm_pipeline: AsmPipeline {
+				strip_comments: true,
+				collapse_whitespace: true,
+				..Default::default()
+			},
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected assembly, got {:?}", response.code);
+		};
+		let asm_lines = response.asm_lines.as_deref().unwrap_or_default();
+		let texts: Vec<_> = asm_lines.iter().map(|l| &*l.text).collect();
+		assert_eq!(texts, code.lines().collect::<Vec<_>>());
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_item_lines_and_stats() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = CompileRequest {
+			code: format!("{ADD_CODE}\n#[inline(never)] pub fn double(a: u8) -> u8 {{ a * 2 }}"),
+			debug_info: true,
+			item: Some("double".into()),
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+
+		let asm_lines = response.asm_lines.as_deref().unwrap_or_default();
+		assert!(asm_lines.iter().any(|l| l.source_line == Some(2)), "{asm_lines:?}");
+		assert!(asm_lines.iter().all(|l| l.source_line != Some(1)), "{asm_lines:?}");
+
+		let stats = response.asm_stats.as_deref().unwrap_or_default();
+		let names: Vec<_> = stats.iter().map(|s| &*s.name).collect();
+		assert!(names.iter().all(|n| n.ends_with("double")), "{names:?}");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_architecture() -> Result<()> {
+		let cases = [
+			(AssemblyArchitecture::Aarch64, "w0, w1, w0"),
+			(AssemblyArchitecture::Wasm32, "i32.add"),
+		];
+
+		for (assembly_architecture, expected) in cases {
+			let coordinator = new_coordinator().await;
+
+			let req = CompileRequest {
+				code: ADD_CODE.into(),
+				assembly_architecture,
+				..ARBITRARY_ASSEMBLY_REQUEST.clone()
+			};
+
+			let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+			assert!(response.success, "stderr: {}", response.stderr);
+			let CompiledCode::CodeStr(code) = &response.code else {
+				panic!("Expected assembly, got {:?}", response.code);
+			};
+			assert_contains!(code, expected);
+
+			coordinator.shutdown().await?;
+		}
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_architecture_binary() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = CompileRequest {
+			code: format!("{ADD_CODE}\nfn main() {{ println!(\"{{}}\", add(1, 2)); }}"),
+			crate_type: CrateType::Binary,
+			assembly_architecture: AssemblyArchitecture::Aarch64,
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected assembly, got {:?}", response.code);
+		};
+		assert_contains!(code, "w0, w1, w0");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_assembly_of_one_function() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let code = format!("{ADD_CODE}\n{SUBTRACT_CODE}");
+		let req =
+			CompileRequest { code, item: Some("sub".into()), ..ARBITRARY_ASSEMBLY_REQUEST.clone() };
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected assembly, got {:?}", response.code);
+		};
+		assert_contains!(code, "::sub:");
+		assert_not_contains!(code, "::add:");
 
 		coordinator.shutdown().await?;
 
@@ -3132,13 +4479,16 @@ mod tests {
 					DEFAULT_ASSEMBLY_PROCESS,
 				),
 				code: ADD_CODE.into(),
-				..ARBITRARY_ASSEMBLY_REQUEST
+				..ARBITRARY_ASSEMBLY_REQUEST.clone()
 			};
 
 			let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 			assert!(response.success, "stderr: {}", response.stderr);
-			assert_contains!(response.code, expected);
+			let CompiledCode::CodeStr(code) = &response.code else {
+				panic!("Expected assembly, got {:?}", response.code);
+			};
+			assert_contains!(code, expected);
 
 			coordinator.shutdown().await?;
 		}
@@ -3166,13 +4516,16 @@ mod tests {
 					DEFAULT_ASSEMBLY_PROCESS,
 				),
 				code: ADD_CODE.into(),
-				..ARBITRARY_ASSEMBLY_REQUEST
+				..ARBITRARY_ASSEMBLY_REQUEST.clone()
 			};
 
 			let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 			assert!(response.success, "stderr: {}", response.stderr);
-			assert_contains!(response.code, expected);
+			let CompiledCode::CodeStr(code) = &response.code else {
+				panic!("Expected assembly, got {:?}", response.code);
+			};
+			assert_contains!(code, expected);
 
 			coordinator.shutdown().await?;
 		}
@@ -3195,16 +4548,19 @@ mod tests {
 					process,
 				),
 				code: ADD_CODE.into(),
-				..ARBITRARY_ASSEMBLY_REQUEST
+				..ARBITRARY_ASSEMBLY_REQUEST.clone()
 			};
 
 			let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 			assert!(response.success, "stderr: {}", response.stderr);
+			let CompiledCode::CodeStr(code) = &response.code else {
+				panic!("Expected assembly, got {:?}", response.code);
+			};
 			if expected {
-				assert_contains!(response.code, ".cfi_startproc");
+				assert_contains!(code, ".cfi_startproc");
 			} else {
-				assert_not_contains!(response.code, ".cfi_startproc");
+				assert_not_contains!(code, ".cfi_startproc");
 			}
 
 			coordinator.shutdown().await?;
@@ -3215,28 +4571,28 @@ mod tests {
 
 	const SUBTRACT_CODE: &str = r#"pub fn sub(a: u8, b: u8) -> u8 { a - b }"#;
 
-	const ARBITRARY_HIR_REQUEST: CompileRequest = CompileRequest {
+	static ARBITRARY_HIR_REQUEST: Lazy<CompileRequest> = Lazy::new(|| CompileRequest {
 		target: CompileTarget::Hir,
-		channel: RustChannel::Nightly,
+		language: RustSpec::new(RustChannel::Nightly, RustEdition::Rust2021).into(),
 		crate_type: CrateType::Library(LibraryType::Lib),
 		mode: Mode::Release,
-		edition: RustEdition::Rust2021,
-		tests: false,
-		backtrace: false,
-		code: String::new(),
-	};
+		..ARBITRARY_COMPILE_REQUEST.clone()
+	});
 
 	#[tokio::test]
 	#[snafu::report]
 	async fn compile_hir() -> Result<()> {
 		let coordinator = new_coordinator().await;
 
-		let req = CompileRequest { code: SUBTRACT_CODE.into(), ..ARBITRARY_HIR_REQUEST };
+		let req = CompileRequest { code: SUBTRACT_CODE.into(), ..ARBITRARY_HIR_REQUEST.clone() };
 
 		let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 		assert!(response.success, "stderr: {}", response.stderr);
-		assert_contains!(response.code, "extern crate std");
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected HIR, got {:?}", response.code);
+		};
+		assert_contains!(code, "extern crate std");
 
 		coordinator.shutdown().await?;
 
@@ -3250,19 +4606,20 @@ mod tests {
 
 		let req = CompileRequest {
 			target: CompileTarget::LlvmIr,
-			channel: RustChannel::Stable,
+			language: RustSpec::new(RustChannel::Stable, RustEdition::Rust2015).into(),
 			crate_type: CrateType::Library(LibraryType::Lib),
 			mode: Mode::Debug,
-			edition: RustEdition::Rust2015,
-			tests: false,
-			backtrace: false,
 			code: r#"pub fn mul(a: u8, b: u8) -> u8 { a * b }"#.into(),
+			..ARBITRARY_COMPILE_REQUEST.clone()
 		};
 
 		let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 		assert!(response.success, "stderr: {}", response.stderr);
-		assert_contains!(response.code, "@llvm.umul.with.overflow.i8(i8, i8)");
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected LLVM IR, got {:?}", response.code);
+		};
+		assert_contains!(code, "@llvm.umul.with.overflow.i8(i8, i8)");
 
 		coordinator.shutdown().await?;
 
@@ -3277,31 +4634,116 @@ mod tests {
 
 		let req = CompileRequest {
 			target: CompileTarget::Wasm,
-			channel: RustChannel::Nightly,
+			language: RustSpec::new(RustChannel::Nightly, RustEdition::Rust2021).into(),
 			crate_type: CrateType::Library(LibraryType::Cdylib),
 			mode: Mode::Release,
-			edition: RustEdition::Rust2021,
-			tests: false,
-			backtrace: false,
 			code: r#"#[export_name = "inc"] pub fn inc(a: u8) -> u8 { a + 1 }"#.into(),
+			..ARBITRARY_COMPILE_REQUEST.clone()
 		};
 
 		let response = coordinator.compile(req).with_timeout().await.unwrap();
 
 		assert!(response.success, "stderr: {}", response.stderr);
-		assert_contains!(response.code, r#"(func $inc (;0;) (type 0) (param i32) (result i32)"#);
+		let CompiledCode::Package(files) = &response.code else {
+			panic!("Expected the package files, got {:?}", response.code);
+		};
+		let paths = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+		assert!(paths.iter().any(|p| p.ends_with("_bg.wasm")), "{paths:?}");
+		assert!(!paths.contains(&".gitignore"), "{paths:?}");
 
 		coordinator.shutdown().await?;
 
 		Ok(())
 	}
 
-	const ARBITRARY_FORMAT_REQUEST: FormatRequest = FormatRequest {
+	const CONSOLE_PRELUDE_CODE: &str = r#"#![allow(unused)]
+mod nested {
+    pub fn greet() { println!("Hello from a module"); }
+}
+
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn greet() {
+    println!("Hello");
+    nested::greet();
+}"#;
+
+	#[test]
+	fn console_prelude_declared_before_code() {
+		let req = CompileRequest {
+			target: CompileTarget::Wasm,
+			crate_type: CrateType::Library(LibraryType::Cdylib),
+			console_prelude: true,
+			code: CONSOLE_PRELUDE_CODE.into(),
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let WriteFileRequest { content, .. } = req.write_main_request();
+		let content = String::from_utf8(content).unwrap();
+
+		assert!(content.starts_with("#![allow(unused)]#[macro_use] mod asa_prelude; \nmod nested"));
+		assert_eq!(content.lines().count(), CONSOLE_PRELUDE_CODE.lines().count());
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn compile_wasm_console_prelude() -> Result<()> {
+		// cargo-wasm only exists inside the container
+		let coordinator = new_coordinator_docker().await;
+
+		let req = CompileRequest {
+			target: CompileTarget::Wasm,
+			crate_type: CrateType::Library(LibraryType::Cdylib),
+			console_prelude: true,
+			code: CONSOLE_PRELUDE_CODE.into(),
+			..ARBITRARY_ASSEMBLY_REQUEST.clone()
+		};
+
+		let response = coordinator.compile(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		let CompiledCode::Package(files) = &response.code else {
+			panic!("Expected the package files, got {:?}", response.code);
+		};
+		let glue = files.iter().filter(|f| f.path.ends_with(".js") && !f.path.contains('/'));
+		let glue = glue.map(|f| String::from_utf8_lossy(&f.content)).collect::<String>();
+		assert_contains!(glue, "asaSetOutput");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_results_are_parsed() {
+		let output = [
+			"running 3 tests",
+			"test tests::renders ... ok",
+			"test tests::clicks ... FAIL",
+			"test tests::slow ... ignored, needs a browser",
+			"test result: FAILED. 1 passed; 1 failed; 1 ignored",
+		]
+		.join("\n");
+
+		let tests = parse_test_results(&output);
+		let tests: Vec<_> = tests.iter().map(|t| (t.name.as_str(), t.outcome)).collect();
+
+		assert_eq!(
+			tests,
+			[
+				("tests::renders", TestOutcome::Passed),
+				("tests::clicks", TestOutcome::Failed),
+				("tests::slow", TestOutcome::Ignored),
+			]
+		);
+	}
+
+	static ARBITRARY_FORMAT_REQUEST: Lazy<FormatRequest> = Lazy::new(|| FormatRequest {
 		channel: RustChannel::Stable,
 		crate_type: CrateType::Binary,
 		edition: RustEdition::Rust2015,
 		code: String::new(),
-	};
+		package_name: "playground".into(),
+	});
 
 	const ARBITRARY_FORMAT_INPUT: &str = "fn main(){1+1;}";
 	#[rustfmt::skip]
@@ -3316,7 +4758,10 @@ mod tests {
 	async fn format() -> Result<()> {
 		let coordinator = new_coordinator().await;
 
-		let req = FormatRequest { code: ARBITRARY_FORMAT_INPUT.into(), ..ARBITRARY_FORMAT_REQUEST };
+		let req = FormatRequest {
+			code: ARBITRARY_FORMAT_INPUT.into(),
+			..ARBITRARY_FORMAT_REQUEST.clone()
+		};
 
 		let response = coordinator.format(req).with_timeout().await.unwrap();
 
@@ -3338,7 +4783,7 @@ mod tests {
 			let req = FormatRequest {
 				channel,
 				code: ARBITRARY_FORMAT_INPUT.into(),
-				..ARBITRARY_FORMAT_REQUEST
+				..ARBITRARY_FORMAT_REQUEST.clone()
 			};
 
 			let response = coordinator.format(req).with_timeout().await.unwrap();
@@ -3369,7 +4814,7 @@ mod tests {
 					edition,
 					code: code.into(),
 					channel: RustChannel::Nightly, // To allow 2024 while it is unstable
-					..ARBITRARY_FORMAT_REQUEST
+					..ARBITRARY_FORMAT_REQUEST.clone()
 				};
 
 				let response = coordinator.format(req).with_timeout().await.unwrap();
@@ -3381,12 +4826,13 @@ mod tests {
 		Ok(())
 	}
 
-	const ARBITRARY_CLIPPY_REQUEST: ClippyRequest = ClippyRequest {
+	static ARBITRARY_CLIPPY_REQUEST: Lazy<ClippyRequest> = Lazy::new(|| ClippyRequest {
 		channel: RustChannel::Stable,
 		crate_type: CrateType::Library(LibraryType::Rlib),
 		edition: RustEdition::Rust2021,
 		code: String::new(),
-	};
+		package_name: "playground".into(),
+	});
 
 	#[tokio::test]
 	#[snafu::report]
@@ -3401,7 +4847,7 @@ mod tests {
                 }
                 "#
 			.into(),
-			..ARBITRARY_CLIPPY_REQUEST
+			..ARBITRARY_CLIPPY_REQUEST.clone()
 		};
 
 		let response = coordinator.clippy(req).with_timeout().await.unwrap();
@@ -3429,8 +4875,11 @@ mod tests {
 				move |(edition, expected_to_be_clean)| async move {
 					let coordinator = new_coordinator().await;
 
-					let req =
-						ClippyRequest { edition, code: code.into(), ..ARBITRARY_CLIPPY_REQUEST };
+					let req = ClippyRequest {
+						edition,
+						code: code.into(),
+						..ARBITRARY_CLIPPY_REQUEST.clone()
+					};
 
 					let response = coordinator.clippy(req).with_timeout().await.unwrap();
 
@@ -3452,12 +4901,21 @@ mod tests {
 		Ok(())
 	}
 
-	const ARBITRARY_MIRI_REQUEST: MiriRequest = MiriRequest {
+	static ARBITRARY_MIRI_REQUEST: Lazy<MiriRequest> = Lazy::new(|| MiriRequest {
 		channel: RustChannel::Nightly,
 		crate_type: CrateType::Binary,
 		edition: RustEdition::Rust2021,
+		tests: false,
+		flags: MiriFlags {
+			isolation: false,
+			aliasing_model: AliasingModel::StackedBorrows,
+			strict_provenance: false,
+			seed: None,
+			many_seeds: None,
+		},
 		code: String::new(),
-	};
+		package_name: "playground".into(),
+	});
 
 	#[tokio::test]
 	#[snafu::report]
@@ -3473,7 +4931,7 @@ mod tests {
                 }
                 "#
 			.into(),
-			..ARBITRARY_MIRI_REQUEST
+			..ARBITRARY_MIRI_REQUEST.clone()
 		};
 
 		let response = coordinator.miri(req).with_timeout().await.unwrap();
@@ -3491,12 +4949,106 @@ mod tests {
 		Ok(())
 	}
 
-	const ARBITRARY_MACRO_EXPANSION_REQUEST: MacroExpansionRequest = MacroExpansionRequest {
-		channel: RustChannel::Nightly,
-		crate_type: CrateType::Library(LibraryType::Cdylib),
-		edition: RustEdition::Rust2018,
-		code: String::new(),
-	};
+	#[tokio::test]
+	#[snafu::report]
+	async fn miri_isolation() -> Result<()> {
+		// cargo-miri-playground only exists inside the container
+		let coordinator = new_coordinator_docker().await;
+
+		let code = r#"
+            fn main() {
+                println!("{:?}", std::time::SystemTime::now());
+            }
+            "#;
+
+		let req = MiriRequest { code: code.into(), ..ARBITRARY_MIRI_REQUEST.clone() };
+		let response = coordinator.miri(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+
+		let req = MiriRequest {
+			code: code.into(),
+			flags: MiriFlags { isolation: true, ..ARBITRARY_MIRI_REQUEST.flags.clone() },
+			..ARBITRARY_MIRI_REQUEST.clone()
+		};
+		let response = coordinator.miri(req).with_timeout().await.unwrap();
+
+		assert!(!response.success, "stderr: {}", response.stderr);
+		assert_contains!(response.stderr, "isolation");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn miri_library_tests_with_tree_borrows() -> Result<()> {
+		let coordinator = new_coordinator_docker().await;
+
+		let req = MiriRequest {
+			crate_type: CrateType::Library(LibraryType::Lib),
+			flags: MiriFlags {
+				aliasing_model: AliasingModel::TreeBorrows,
+				..ARBITRARY_MIRI_REQUEST.flags.clone()
+			},
+			code: r#"
+                #[test]
+                fn write_through_shared_reference() {
+                    let mut x = 0u8;
+                    let r = &x as *const u8 as *mut u8;
+                    unsafe { *r = 1; }
+                    x += 1;
+                }
+                "#
+			.into(),
+			..ARBITRARY_MIRI_REQUEST.clone()
+		};
+
+		let response = coordinator.miri(req).with_timeout().await.unwrap();
+
+		assert!(!response.success, "stderr: {}", response.stderr);
+
+		assert_contains!(response.stdout, "write_through_shared_reference");
+		assert_contains!(response.stderr, "Undefined Behavior");
+		assert_contains!(response.stderr, "Frozen");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn miri_flags() {
+		let flags = MiriFlags {
+			isolation: true,
+			aliasing_model: AliasingModel::TreeBorrows,
+			strict_provenance: true,
+			seed: Some(3),
+			many_seeds: Some(16),
+		};
+
+		assert_eq!(
+			flags.to_miri_flags(),
+			[
+				"-Zmiri-tree-borrows",
+				"-Zmiri-strict-provenance",
+				"-Zmiri-seed=3",
+				"-Zmiri-many-seeds=0..16",
+			]
+		);
+		assert_eq!(MiriFlags::default().to_miri_flags(), ["-Zmiri-disable-isolation"]);
+	}
+
+	static ARBITRARY_MACRO_EXPANSION_REQUEST: Lazy<MacroExpansionRequest> =
+		Lazy::new(|| MacroExpansionRequest {
+			channel: RustChannel::Nightly,
+			crate_type: CrateType::Library(LibraryType::Cdylib),
+			edition: RustEdition::Rust2018,
+			item: None,
+			code: String::new(),
+			package_name: "playground".into(),
+		});
 
 	#[tokio::test]
 	#[snafu::report]
@@ -3511,7 +5063,7 @@ mod tests {
                 fn main() { println!("Hello!"); }
                 "#
 			.into(),
-			..ARBITRARY_MACRO_EXPANSION_REQUEST
+			..ARBITRARY_MACRO_EXPANSION_REQUEST.clone()
 		};
 
 		let response = coordinator.macro_expansion(req).with_timeout().await.unwrap();
@@ -3525,6 +5077,53 @@ mod tests {
 		Ok(())
 	}
 
+	#[tokio::test]
+	#[snafu::report]
+	async fn macro_expansion_of_one_item() -> Result<()> {
+		let coordinator = new_coordinator().await;
+
+		let req = MacroExpansionRequest {
+			item: Some("Dummy".into()),
+			code: r#"
+                #[derive(Debug)]
+                struct Dummy;
+
+                fn main() { println!("Hello!"); }
+                "#
+			.into(),
+			..ARBITRARY_MACRO_EXPANSION_REQUEST.clone()
+		};
+
+		let response = coordinator.macro_expansion(req).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		assert_contains!(response.stdout, "impl ::core::fmt::Debug for Dummy");
+		assert_not_contains!(response.stdout, "Hello!");
+		assert_not_contains!(response.stdout, "extern crate std");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn requests_use_their_package_project() {
+		let execute =
+			ExecuteRequest { package_name: "slide".into(), ..ARBITRARY_EXECUTE_REQUEST.clone() };
+		assert_eq!(execute.create_project_request().path, "packages/slide");
+		assert_eq!(execute.write_main_request().path, "packages/slide/src/main.rs");
+		assert_eq!(execute.delete_previous_main_request().path, "packages/slide/src/lib.rs");
+		assert_eq!(execute.execute_cargo_request().cwd.as_deref(), Some("packages/slide"));
+		assert_eq!(CargoTomlModifier::project_dir(&execute), "packages/slide");
+
+		let format =
+			FormatRequest { package_name: "slide".into(), ..ARBITRARY_FORMAT_REQUEST.clone() };
+		assert_eq!(format.read_main_request().path, "packages/slide/src/main.rs");
+
+		let miri = MiriRequest { package_name: "slide".into(), ..ARBITRARY_MIRI_REQUEST.clone() };
+		assert_eq!(miri.execute_cargo_request().cwd.as_deref(), Some("packages/slide"));
+	}
+
 	// The next set of tests are broader than the functionality of a
 	// single operation.
 
@@ -3542,6 +5141,9 @@ mod tests {
 			tests: false,
 			backtrace: false,
 			code: "pub fn alpha() {}".into(),
+			package_name: "playground".into(),
+			statistics_interval: None,
+			output_limit: None,
 		};
 
 		let response = coordinator.execute(req.clone()).with_timeout().await.unwrap();
@@ -3551,20 +5153,22 @@ mod tests {
 		// Create a lib.rs file
 		let req = CompileRequest {
 			target: CompileTarget::LlvmIr,
-			channel: req.channel,
+			language: RustSpec::new(req.channel, req.edition).into(),
 			mode: req.mode,
-			edition: req.edition,
 			crate_type: CrateType::Library(LibraryType::Rlib),
-			tests: req.tests,
-			backtrace: req.backtrace,
 			code: "pub fn beta() {}".into(),
+			package_name: req.package_name,
+			..ARBITRARY_COMPILE_REQUEST.clone()
 		};
 
 		let response = coordinator.compile(req.clone()).with_timeout().await.unwrap();
 		assert!(response.success, "stderr: {}", response.stderr);
 
-		assert_not_contains!(response.code, "alpha");
-		assert_contains!(response.code, "beta");
+		let CompiledCode::CodeStr(code) = &response.code else {
+			panic!("Expected LLVM IR, got {:?}", response.code);
+		};
+		assert_not_contains!(code, "alpha");
+		assert_contains!(code, "beta");
 
 		coordinator.shutdown().await?;
 
@@ -3584,6 +5188,9 @@ mod tests {
 			tests: false,
 			backtrace: false,
 			code: r#"fn main() { println!("hello") }"#.into(),
+			package_name: "playground".into(),
+			statistics_interval: None,
+			output_limit: None,
 		};
 
 		let res = coordinator.execute(req.clone()).await.unwrap();
@@ -3612,6 +5219,9 @@ mod tests {
 			tests: false,
 			backtrace: false,
 			code: r#"fn main() { std::process::abort(); }"#.into(),
+			package_name: "playground".into(),
+			statistics_interval: None,
+			output_limit: None,
 		};
 
 		let res = coordinator.execute(req.clone()).await.unwrap();
@@ -3633,6 +5243,9 @@ mod tests {
 			tests: false,
 			backtrace: false,
 			code: Default::default(),
+			package_name: "playground".into(),
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 
@@ -3757,6 +5370,59 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn cargo_cache_usage_adds_directories() {
+		let output = "1048576\t/playground/target\n2048\t/playground/.cargo/registry\n";
+
+		assert_eq!(CargoCache::parse_usage(output), 1_050_624);
+		assert_eq!(CargoCache::parse_usage(""), 0);
+	}
+
+	#[test]
+	fn cargo_cache_trimmed_when_over_limit() {
+		let unlimited = CargoCache::default();
+		let limited = CargoCache { max_size_bytes: Some(1024), ..CargoCache::default() };
+
+		assert!(!unlimited.needs_trim(u64::MAX));
+		assert!(!limited.needs_trim(1024));
+		assert!(limited.needs_trim(1025));
+	}
+
+	#[derive(Debug)]
+	struct FailingCargoCacheBackend(TestBackend);
+
+	impl Backend for FailingCargoCacheBackend {
+		fn prepare_worker_command(&self, channel: RustChannel) -> (Command, Option<Command>) {
+			self.0.prepare_worker_command(channel)
+		}
+
+		fn prepare_cargo_cache(
+			&self,
+			_channel: RustChannel,
+		) -> BoxFuture<'_, Result<(), CargoCacheError>> {
+			async { cargo_cache_error::CleanFailedSnafu { stderr: "volume is in use" }.fail() }
+				.boxed()
+		}
+	}
+
+	#[tokio::test]
+	#[snafu::report]
+	async fn cargo_cache_failure_does_not_stop_worker() -> Result<()> {
+		let coordinator = RestrictedCoordinator::with(|| {
+			Coordinator::new(FailingCargoCacheBackend(TestBackend::new()))
+		})
+		.await;
+
+		let response = coordinator.execute(new_execute_request()).with_timeout().await.unwrap();
+
+		assert!(response.success, "stderr: {}", response.stderr);
+		assert_contains!(response.stdout, "Hello, coordinator!");
+
+		coordinator.shutdown().await?;
+
+		Ok(())
+	}
+
 	static TIMEOUT: Lazy<Duration> = Lazy::new(|| {
 		let millis = env::var("TESTS_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5000);
 		Duration::from_millis(millis)
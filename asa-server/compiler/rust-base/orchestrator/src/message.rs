@@ -1,11 +1,17 @@
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 pub type JobId = u64;
 pub type Path = String;
 
+/// Bumped whenever a change to these messages prevents a coordinator
+/// and a worker built from different sources from understanding each
+/// other.
+pub const PROTOCOL_VERSION: u32 = 5;
+
 macro_rules! impl_narrow_to_broad {
     ($enum_type:ident, $($variant_name:ident => $variant_type:ident),* $(,)?) => {
         $(
@@ -23,37 +29,92 @@ pub struct Multiplexed<T>(pub JobId, pub T);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum CoordinatorMessage {
+	/// Must stay the first variant so that it can be decoded by a worker
+	/// speaking any protocol version.
+	Hello(HelloRequest),
+	CreateProject(CreateProjectRequest),
 	WriteFile(WriteFileRequest),
+	WriteFiles(WriteFilesRequest),
 	DeleteFile(DeleteFileRequest),
+	RemoveDirectory(RemoveDirectoryRequest),
 	ReadFile(ReadFileRequest),
+	ReadFiles(ReadFilesRequest),
+	ListDirectory(ListDirectoryRequest),
+	Stat(StatRequest),
 	ExecuteCommand(ExecuteCommandRequest),
 	StdinPacket(String),
+	StdinBytes(Vec<u8>),
 	StdinClose,
 	Kill,
 }
 
 impl_narrow_to_broad!(
 	CoordinatorMessage,
+	Hello => HelloRequest,
+	CreateProject => CreateProjectRequest,
 	WriteFile => WriteFileRequest,
+	WriteFiles => WriteFilesRequest,
 	DeleteFile => DeleteFileRequest,
+	RemoveDirectory => RemoveDirectoryRequest,
 	ReadFile => ReadFileRequest,
+	ReadFiles => ReadFilesRequest,
+	ListDirectory => ListDirectoryRequest,
+	Stat => StatRequest,
 	ExecuteCommand => ExecuteCommandRequest,
 );
 
 #[derive(Debug, Serialize, Deserialize, strum_macros::AsRefStr)]
 pub enum WorkerMessage {
+	/// Must stay the first variant so that it can be decoded by a
+	/// coordinator speaking any protocol version.
+	Hello(HelloResponse),
+	CreateProject(CreateProjectResponse),
 	WriteFile(WriteFileResponse),
+	WriteFiles(WriteFilesResponse),
 	DeleteFile(DeleteFileResponse),
+	RemoveDirectory(RemoveDirectoryResponse),
 	ReadFile(ReadFileResponse),
+	ReadFiles(ReadFilesResponse),
+	ListDirectory(ListDirectoryResponse),
+	Stat(StatResponse),
 	ExecuteCommand(ExecuteCommandResponse),
 	StdoutPacket(String),
 	StderrPacket(String),
+	/// Output that is not valid UTF-8.
+	StdoutBytes(Vec<u8>),
+	/// Output that is not valid UTF-8.
+	StderrBytes(Vec<u8>),
 	CommandStatistics(CommandStatistics),
-	/// Vestigial; remove after a while
-	Error(SerializedError),
 	Error2(SerializedError2),
 }
 
+/// Child output is sent as text while it is valid UTF-8; anything else
+/// is sent as the raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputChunk {
+	Text(String),
+	Bytes(Vec<u8>),
+}
+
+impl OutputChunk {
+	pub fn into_bytes(self) -> Vec<u8> {
+		match self {
+			OutputChunk::Text(s) => s.into_bytes(),
+			OutputChunk::Bytes(b) => b,
+		}
+	}
+}
+
+/// Invalid UTF-8 is replaced.
+impl From<OutputChunk> for String {
+	fn from(chunk: OutputChunk) -> Self {
+		match chunk {
+			OutputChunk::Text(s) => s,
+			OutputChunk::Bytes(b) => String::from_utf8_lossy(&b).into_owned(),
+		}
+	}
+}
+
 macro_rules! impl_broad_to_narrow_with_error {
     ($enum_type:ident, $($variant_name:ident => $variant_type:ty),* $(,)?) => {
         $(
@@ -63,7 +124,6 @@ macro_rules! impl_broad_to_narrow_with_error {
                 fn try_from(other: $enum_type) -> Result<Self, Self::Error> {
                     match other {
                         $enum_type::$variant_name(x) => Ok(Ok(x)),
-                        $enum_type::Error(e) => Ok(Err(SerializedError2::adapt(e))),
                         $enum_type::Error2(e) => Ok(Err(e)),
                         o => Err(o)
                     }
@@ -75,21 +135,94 @@ macro_rules! impl_broad_to_narrow_with_error {
 
 impl_narrow_to_broad!(
 	WorkerMessage,
+	Hello => HelloResponse,
+	CreateProject => CreateProjectResponse,
 	WriteFile => WriteFileResponse,
+	WriteFiles => WriteFilesResponse,
 	DeleteFile => DeleteFileResponse,
+	RemoveDirectory => RemoveDirectoryResponse,
 	ReadFile => ReadFileResponse,
+	ReadFiles => ReadFilesResponse,
+	ListDirectory => ListDirectoryResponse,
+	Stat => StatResponse,
 	ExecuteCommand => ExecuteCommandResponse,
 	CommandStatistics => CommandStatistics,
 );
 
 impl_broad_to_narrow_with_error!(
 	WorkerMessage,
+	Hello => HelloResponse,
+	CreateProject => CreateProjectResponse,
 	WriteFile => WriteFileResponse,
+	WriteFiles => WriteFilesResponse,
 	DeleteFile => DeleteFileResponse,
+	RemoveDirectory => RemoveDirectoryResponse,
 	ReadFile => ReadFileResponse,
+	ReadFiles => ReadFilesResponse,
+	ListDirectory => ListDirectoryResponse,
+	Stat => StatResponse,
 	ExecuteCommand => ExecuteCommandResponse,
 );
 
+/// Sent by the coordinator before any other request. The layout of this
+/// request and its response must never change; new functionality is
+/// advertised through [`Capability`] instead.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HelloRequest {
+	pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelloResponse {
+	pub protocol_version: u32,
+	pub build: WorkerBuildInfo,
+	pub capabilities: Vec<Capability>,
+	/// The absolute path that the worker's paths are relative to, as
+	/// the compiler sees it.
+	pub project_dir: Path,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerBuildInfo {
+	pub version: String,
+	pub commit: Option<String>,
+}
+
+impl fmt::Display for WorkerBuildInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let commit = self.commit.as_deref().unwrap_or("unknown commit");
+		write!(f, "{} ({commit})", self.version)
+	}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Capability {
+	CreateProject,
+	FilesystemOperations,
+	ConfinedPaths,
+	/// The image has the `wasm-test` Cargo subcommand.
+	WasmTest,
+	/// The image passes `PLAYGROUND_MIRI_FLAGS` on to Miri.
+	MiriFlags,
+	/// Stdin is accepted as [`CoordinatorMessage::StdinBytes`].
+	StdinBytes,
+	/// Advertised by a newer worker; unknown to this coordinator.
+	#[serde(other)]
+	Unknown,
+}
+
+/// Creates a project in a subdirectory of the base project, using the
+/// base project's manifest and lockfile as a template. The new project
+/// shares the base project's `target` directory so that pre-built
+/// dependencies are reused. Existing files are left untouched.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProjectRequest {
+	pub path: Path,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProjectResponse(pub ());
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WriteFileRequest {
 	pub path: Path,
@@ -99,6 +232,14 @@ pub struct WriteFileRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WriteFileResponse(pub ());
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WriteFilesRequest {
+	pub files: Vec<WriteFileRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WriteFilesResponse(pub ());
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteFileRequest {
 	pub path: Path,
@@ -107,6 +248,15 @@ pub struct DeleteFileRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteFileResponse(pub ());
 
+/// Recursively removes a directory and everything inside it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveDirectoryRequest {
+	pub path: Path,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveDirectoryResponse(pub ());
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReadFileRequest {
 	pub path: Path,
@@ -115,12 +265,69 @@ pub struct ReadFileRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReadFileResponse(pub Vec<u8>);
 
+/// Reads several files at once. Paths naming a directory read every
+/// file inside of it, recursively, skipping symlinks. Paths that link to
+/// outside of the project are rejected.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadFilesRequest {
+	pub paths: Vec<Path>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadFilesResponse(pub Vec<FileContent>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileContent {
+	pub path: Path,
+	pub content: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListDirectoryRequest {
+	pub path: Path,
+	pub recursive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListDirectoryResponse(pub Vec<DirectoryEntry>);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectoryEntry {
+	pub path: Path,
+	pub metadata: FileMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatRequest {
+	pub path: Path,
+}
+
+/// `None` when the path does not exist.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatResponse(pub Option<FileMetadata>);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileKind {
+	File,
+	Directory,
+	Symlink,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMetadata {
+	pub kind: FileKind,
+	pub size_bytes: u64,
+	pub modified_unix_secs: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExecuteCommandRequest {
 	pub cmd: String,
 	pub args: Vec<String>,
 	pub envs: HashMap<String, String>,
 	pub cwd: Option<String>, // None means in project direcotry.
+	pub statistics_interval: Option<Duration>, // None means every second.
+	pub output_limit: Option<OutputLimit>, // None means the default limit.
 }
 
 impl ExecuteCommandRequest {
@@ -133,27 +340,56 @@ impl ExecuteCommandRequest {
 			args: args.into_iter().map(Into::into).collect(),
 			envs: Default::default(),
 			cwd: None,
+			statistics_interval: None,
+			output_limit: None,
 		}
 	}
 }
 
+/// Applies to stdout and stderr separately.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLimit {
+	pub bytes: usize,
+	pub mode: OutputLimitMode,
+}
+
+impl Default for OutputLimit {
+	fn default() -> Self { Self { bytes: 640 * 1024, mode: OutputLimitMode::Abort } }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputLimitMode {
+	/// Kill the process once it exceeds the limit.
+	#[default]
+	Abort,
+	/// Only keep the first and last half of the limit, replacing the
+	/// rest of the output with a note saying how much was omitted.
+	Truncate,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExecuteCommandResponse {
 	pub success: bool,
 	pub exit_detail: String,
+	/// The last statistics sampled before the process exited.
+	pub statistics: Option<CommandStatistics>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Covers the process and, where supported, all of its descendants.
+/// Statistics that aren't available on the current platform are `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CommandStatistics {
 	pub total_time_secs: f64,
+	pub user_time_secs: f64,
+	pub system_time_secs: f64,
 	pub resident_set_size_bytes: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SerializedError(pub String);
-
-impl SerializedError {
-	pub fn new(e: impl snafu::Error) -> Self { Self(snafu::Report::from_error(e).to_string()) }
+	/// The largest `resident_set_size_bytes` sampled so far.
+	pub peak_resident_set_size_bytes: u64,
+	pub voluntary_context_switches: Option<u64>,
+	pub involuntary_context_switches: Option<u64>,
+	pub read_bytes: Option<u64>,
+	pub written_bytes: Option<u64>,
+	pub threads: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -177,8 +413,6 @@ impl SerializedError2 {
 
 		Self { message, source }
 	}
-
-	pub fn adapt(other: SerializedError) -> Self { Self { message: other.0, source: None } }
 }
 
 impl snafu::Error for SerializedError2 {
@@ -195,18 +429,46 @@ pub trait OneToOneResponse {
 	type Response;
 }
 
+impl OneToOneResponse for HelloRequest {
+	type Response = HelloResponse;
+}
+
+impl OneToOneResponse for CreateProjectRequest {
+	type Response = CreateProjectResponse;
+}
+
 impl OneToOneResponse for WriteFileRequest {
 	type Response = WriteFileResponse;
 }
 
+impl OneToOneResponse for WriteFilesRequest {
+	type Response = WriteFilesResponse;
+}
+
 impl OneToOneResponse for DeleteFileRequest {
 	type Response = DeleteFileResponse;
 }
 
+impl OneToOneResponse for RemoveDirectoryRequest {
+	type Response = RemoveDirectoryResponse;
+}
+
 impl OneToOneResponse for ReadFileRequest {
 	type Response = ReadFileResponse;
 }
 
+impl OneToOneResponse for ReadFilesRequest {
+	type Response = ReadFilesResponse;
+}
+
+impl OneToOneResponse for ListDirectoryRequest {
+	type Response = ListDirectoryResponse;
+}
+
+impl OneToOneResponse for StatRequest {
+	type Response = StatResponse;
+}
+
 impl OneToOneResponse for ExecuteCommandRequest {
 	type Response = ExecuteCommandResponse;
 }
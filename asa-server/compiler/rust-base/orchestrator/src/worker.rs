@@ -30,10 +30,11 @@
 //! - process stderr
 //!   - [`tokio::process::ChildStderr`][]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
 
 use snafu::prelude::*;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
@@ -44,9 +45,14 @@ use tokio::{fs, select};
 use tokio_util::sync::CancellationToken;
 
 use crate::message::{
-	CoordinatorMessage, DeleteFileRequest, DeleteFileResponse, ExecuteCommandRequest,
-	ExecuteCommandResponse, JobId, Multiplexed, ReadFileRequest, ReadFileResponse,
-	SerializedError2, WorkerMessage, WriteFileRequest, WriteFileResponse,
+	Capability, CommandStatistics, CoordinatorMessage, CreateProjectRequest, CreateProjectResponse,
+	DeleteFileRequest, DeleteFileResponse, DirectoryEntry, ExecuteCommandRequest,
+	ExecuteCommandResponse, FileContent, FileKind, FileMetadata, HelloResponse, JobId,
+	ListDirectoryRequest, ListDirectoryResponse, Multiplexed, OutputChunk, OutputLimit,
+	OutputLimitMode, ReadFileRequest, ReadFileResponse, ReadFilesRequest, ReadFilesResponse,
+	RemoveDirectoryRequest, RemoveDirectoryResponse, SerializedError2, StatRequest, StatResponse,
+	WorkerBuildInfo, WorkerMessage, WriteFileRequest, WriteFileResponse, WriteFilesRequest,
+	WriteFilesResponse, PROTOCOL_VERSION,
 };
 use crate::{bincode_input_closed, DropErrorDetailsExt};
 
@@ -126,6 +132,34 @@ async fn handle_coordinator_message(
 				};
 
 				match coordinator_msg {
+					CoordinatorMessage::Hello(_) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							// The compiler records paths with symlinks resolved
+							let project_dir =
+								fs::canonicalize(&project_dir).await.unwrap_or(project_dir);
+
+							worker_msg_tx
+								.send_ok(hello_response(&project_dir))
+								.await
+								.context(UnableToSendHelloResponseSnafu)
+						});
+					}
+
+					CoordinatorMessage::CreateProject(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_create_project(req, project_dir).await)
+								.await
+								.context(UnableToSendCreateProjectResponseSnafu)
+						});
+					}
+
 					CoordinatorMessage::WriteFile(req) => {
 						let project_dir = project_dir.clone();
 						let worker_msg_tx = worker_msg_tx();
@@ -138,6 +172,18 @@ async fn handle_coordinator_message(
 						});
 					}
 
+					CoordinatorMessage::WriteFiles(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_write_files(req, project_dir).await)
+								.await
+								.context(UnableToSendWriteFilesResponseSnafu)
+						});
+					}
+
 					CoordinatorMessage::DeleteFile(req) => {
 						let project_dir = project_dir.clone();
 						let worker_msg_tx = worker_msg_tx();
@@ -150,6 +196,18 @@ async fn handle_coordinator_message(
 						});
 					}
 
+					CoordinatorMessage::RemoveDirectory(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_remove_directory(req, project_dir).await)
+								.await
+								.context(UnableToSendRemoveDirectoryResponseSnafu)
+						});
+					}
+
 					CoordinatorMessage::ReadFile(req) => {
 						let project_dir = project_dir.clone();
 						let worker_msg_tx = worker_msg_tx();
@@ -162,6 +220,42 @@ async fn handle_coordinator_message(
 						});
 					}
 
+					CoordinatorMessage::ReadFiles(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_read_files(req, project_dir).await)
+								.await
+								.context(UnableToSendReadFilesResponseSnafu)
+						});
+					}
+
+					CoordinatorMessage::ListDirectory(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_list_directory(req, project_dir).await)
+								.await
+								.context(UnableToSendListDirectoryResponseSnafu)
+						});
+					}
+
+					CoordinatorMessage::Stat(req) => {
+						let project_dir = project_dir.clone();
+						let worker_msg_tx = worker_msg_tx();
+
+						tasks.spawn(async move {
+							worker_msg_tx
+								.send(handle_stat(req, project_dir).await)
+								.await
+								.context(UnableToSendStatResponseSnafu)
+						});
+					}
+
 					CoordinatorMessage::ExecuteCommand(req) => {
 						process_tx
 							.send(Multiplexed(job_id, ProcessCommand::Start(req, worker_msg_tx())))
@@ -171,6 +265,14 @@ async fn handle_coordinator_message(
 					}
 
 					CoordinatorMessage::StdinPacket(data) => {
+						process_tx
+							.send(Multiplexed(job_id, ProcessCommand::Stdin(data.into_bytes())))
+							.await
+							.drop_error_details()
+							.context(UnableToSendStdinPacketSnafu)?;
+					}
+
+					CoordinatorMessage::StdinBytes(data) => {
 						process_tx
 							.send(Multiplexed(job_id, ProcessCommand::Stdin(data)))
 							.await
@@ -208,15 +310,36 @@ async fn handle_coordinator_message(
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum HandleCoordinatorMessageError {
+	#[snafu(display("Could not send the hello response to the coordinator"))]
+	UnableToSendHelloResponse { source: MultiplexingSenderError },
+
+	#[snafu(display("Could not send the create project response to the coordinator"))]
+	UnableToSendCreateProjectResponse { source: MultiplexingSenderError },
+
 	#[snafu(display("Could not send the write command response to the coordinator"))]
 	UnableToSendWriteFileResponse { source: MultiplexingSenderError },
 
+	#[snafu(display("Could not send the write files command response to the coordinator"))]
+	UnableToSendWriteFilesResponse { source: MultiplexingSenderError },
+
 	#[snafu(display("Could not send the delete command response to the coordinator"))]
 	UnableToSendDeleteFileResponse { source: MultiplexingSenderError },
 
+	#[snafu(display("Could not send the remove directory command response to the coordinator"))]
+	UnableToSendRemoveDirectoryResponse { source: MultiplexingSenderError },
+
 	#[snafu(display("Could not send the read command response to the coordinator"))]
 	UnableToSendReadFileResponse { source: MultiplexingSenderError },
 
+	#[snafu(display("Could not send the read files command response to the coordinator"))]
+	UnableToSendReadFilesResponse { source: MultiplexingSenderError },
+
+	#[snafu(display("Could not send the list directory command response to the coordinator"))]
+	UnableToSendListDirectoryResponse { source: MultiplexingSenderError },
+
+	#[snafu(display("Could not send the stat command response to the coordinator"))]
+	UnableToSendStatResponse { source: MultiplexingSenderError },
+
 	#[snafu(display("Failed to send command execution request to the command task"))]
 	UnableToSendCommandExecutionRequest { source: mpsc::error::SendError<()> },
 
@@ -279,13 +402,107 @@ pub enum MultiplexingSenderError {
 	UnableToSendWorkerMessage { source: mpsc::error::SendError<()> },
 }
 
+const CAPABILITIES: [Capability; 6] = [
+	Capability::CreateProject,
+	Capability::FilesystemOperations,
+	Capability::ConfinedPaths,
+	Capability::WasmTest,
+	Capability::MiriFlags,
+	Capability::StdinBytes,
+];
+
+// The coordinator decides whether it can work with us.
+fn hello_response(project_dir: &Path) -> HelloResponse {
+	let build = WorkerBuildInfo {
+		version: env!("CARGO_PKG_VERSION").into(),
+		commit: option_env!("ASA_BUILD_COMMIT").filter(|c| !c.is_empty()).map(Into::into),
+	};
+
+	HelloResponse {
+		protocol_version: PROTOCOL_VERSION,
+		build,
+		capabilities: CAPABILITIES.into(),
+		project_dir: project_dir.to_string_lossy().into_owned(),
+	}
+}
+
+const PROJECT_TEMPLATE_FILES: [&str; 2] = ["Cargo.toml", "Cargo.lock"];
+
+async fn handle_create_project(
+	req: CreateProjectRequest,
+	project_dir: PathBuf,
+) -> Result<CreateProjectResponse, CreateProjectError> {
+	use create_project_error::*;
+
+	let relative_path = Path::new(&req.path);
+	let depth = relative_path.components().count();
+	let is_nested =
+		relative_path.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+	ensure!(depth > 0 && is_nested, InvalidPathSnafu { path: req.path });
+
+	let path = project_dir.join(relative_path);
+
+	let cargo_config_dir = path.join(".cargo");
+	fs::create_dir_all(&cargo_config_dir)
+		.await
+		.context(UnableToCreateDirSnafu { dir: &cargo_config_dir })?;
+
+	for file in PROJECT_TEMPLATE_FILES {
+		let from = project_dir.join(file);
+		let to = path.join(file);
+
+		if file_exists(&to).await? || !file_exists(&from).await? {
+			continue;
+		}
+
+		fs::copy(&from, &to).await.context(UnableToCopyTemplateSnafu { from, to })?;
+	}
+
+	// Share the pre-built dependencies of the base project.
+	let cargo_config = cargo_config_dir.join("config.toml");
+	if !file_exists(&cargo_config).await? {
+		let target_dir = "../".repeat(depth) + "target";
+		let content = format!("[build]\ntarget-dir = \"{target_dir}\"\n");
+		fs::write(&cargo_config, content)
+			.await
+			.context(UnableToWriteCargoConfigSnafu { path: cargo_config })?;
+	}
+
+	Ok(CreateProjectResponse(()))
+}
+
+async fn file_exists(path: &Path) -> Result<bool, CreateProjectError> {
+	use create_project_error::*;
+
+	fs::try_exists(path).await.context(UnableToCheckFileSnafu { path })
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum CreateProjectError {
+	#[snafu(display("The project path {path} must be relative and inside the base project"))]
+	InvalidPath { path: String },
+
+	#[snafu(display("Failed to create project directory {}", dir.display()))]
+	UnableToCreateDir { source: std::io::Error, dir: PathBuf },
+
+	#[snafu(display("Failed to check if {} exists", path.display()))]
+	UnableToCheckFile { source: std::io::Error, path: PathBuf },
+
+	#[snafu(display("Failed to copy {} to {}", from.display(), to.display()))]
+	UnableToCopyTemplate { source: std::io::Error, from: PathBuf, to: PathBuf },
+
+	#[snafu(display("Failed to write Cargo configuration {}", path.display()))]
+	UnableToWriteCargoConfig { source: std::io::Error, path: PathBuf },
+}
+
 async fn handle_write_file(
 	req: WriteFileRequest,
 	project_dir: PathBuf,
 ) -> Result<WriteFileResponse, WriteFileError> {
 	use write_file_error::*;
 
-	let path = parse_working_dir(Some(req.path), project_dir);
+	let path = project_path(req.path, &project_dir, true).await.context(InvalidPathSnafu)?;
 
 	// Create intermediate directories.
 	if let Some(parent_dir) = path.parent() {
@@ -300,6 +517,9 @@ async fn handle_write_file(
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum WriteFileError {
+	#[snafu(display("The file path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
 	#[snafu(display("Failed to create parent directory {}", parent_dir.display()))]
 	UnableToCreateDir { source: std::io::Error, parent_dir: PathBuf },
 
@@ -307,13 +527,34 @@ pub enum WriteFileError {
 	UnableToWriteFile { source: std::io::Error, path: PathBuf },
 }
 
+async fn handle_write_files(
+	req: WriteFilesRequest,
+	project_dir: PathBuf,
+) -> Result<WriteFilesResponse, WriteFilesError> {
+	use write_files_error::*;
+
+	for file in req.files {
+		let path = file.path.clone();
+		handle_write_file(file, project_dir.clone()).await.context(UnableToWriteSnafu { path })?;
+	}
+
+	Ok(WriteFilesResponse(()))
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum WriteFilesError {
+	#[snafu(display("Failed to write {path}"))]
+	UnableToWrite { source: WriteFileError, path: String },
+}
+
 async fn handle_delete_file(
 	req: DeleteFileRequest,
 	project_dir: PathBuf,
 ) -> Result<DeleteFileResponse, DeleteFileError> {
 	use delete_file_error::*;
 
-	let path = parse_working_dir(Some(req.path), project_dir);
+	let path = project_path(req.path, &project_dir, false).await.context(InvalidPathSnafu)?;
 
 	let r = match fs::remove_file(&path).await {
 		Ok(()) => Ok(()),
@@ -328,17 +569,52 @@ async fn handle_delete_file(
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum DeleteFileError {
+	#[snafu(display("The file path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
 	#[snafu(display("Failed to delete file {}", path.display()))]
 	UnableToDeleteFile { source: std::io::Error, path: PathBuf },
 }
 
+async fn handle_remove_directory(
+	req: RemoveDirectoryRequest,
+	project_dir: PathBuf,
+) -> Result<RemoveDirectoryResponse, RemoveDirectoryError> {
+	use remove_directory_error::*;
+
+	let path = project_path(req.path, &project_dir, false).await.context(InvalidPathSnafu)?;
+	ensure!(path != project_dir, RemovingProjectDirectorySnafu);
+
+	let r = match fs::remove_dir_all(&path).await {
+		Ok(()) => Ok(()),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+		Err(e) => Err(e),
+	};
+
+	r.context(UnableToRemoveDirectorySnafu { path })?;
+	Ok(RemoveDirectoryResponse(()))
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum RemoveDirectoryError {
+	#[snafu(display("Refusing to remove the project directory"))]
+	RemovingProjectDirectory,
+
+	#[snafu(display("The directory path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
+	#[snafu(display("Failed to remove directory {}", path.display()))]
+	UnableToRemoveDirectory { source: std::io::Error, path: PathBuf },
+}
+
 async fn handle_read_file(
 	req: ReadFileRequest,
 	project_dir: PathBuf,
 ) -> Result<ReadFileResponse, ReadFileError> {
 	use read_file_error::*;
 
-	let path = parse_working_dir(Some(req.path), project_dir);
+	let path = project_path(req.path, &project_dir, true).await.context(InvalidPathSnafu)?;
 
 	let content = fs::read(&path).await.context(UnableToReadFileSnafu { path })?;
 
@@ -348,23 +624,282 @@ async fn handle_read_file(
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum ReadFileError {
+	#[snafu(display("The file path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
+	#[snafu(display("Failed to read file {}", path.display()))]
+	UnableToReadFile { source: std::io::Error, path: PathBuf },
+}
+
+async fn handle_read_files(
+	req: ReadFilesRequest,
+	project_dir: PathBuf,
+) -> Result<ReadFilesResponse, ReadFilesError> {
+	use read_files_error::*;
+
+	let mut paths = Vec::new();
+	for relative_path in req.paths {
+		let path = project_path(relative_path.clone(), &project_dir, true)
+			.await
+			.context(InvalidPathSnafu)?;
+
+		let metadata = fs::metadata(&path).await.context(UnableToStatSnafu { path: &path })?;
+
+		if metadata.is_dir() {
+			let entries = walk_directory(&path, &relative_path, true)
+				.await
+				.context(UnableToListDirectorySnafu { path })?;
+
+			paths.extend(
+				entries
+					.into_iter()
+					.filter(|entry| entry.metadata.kind == FileKind::File)
+					.map(|entry| entry.path),
+			);
+		} else {
+			paths.push(relative_path);
+		}
+	}
+
+	let mut files = Vec::with_capacity(paths.len());
+	for relative_path in paths {
+		let path = project_path(relative_path.clone(), &project_dir, true)
+			.await
+			.context(InvalidPathSnafu)?;
+		let content = fs::read(&path).await.context(UnableToReadFileSnafu { path })?;
+		files.push(FileContent { path: relative_path, content });
+	}
+
+	Ok(ReadFilesResponse(files))
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum ReadFilesError {
+	#[snafu(display("The file path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
+	#[snafu(display("Failed to get information about {}", path.display()))]
+	UnableToStat { source: std::io::Error, path: PathBuf },
+
+	#[snafu(display("Failed to list directory {}", path.display()))]
+	UnableToListDirectory { source: std::io::Error, path: PathBuf },
+
 	#[snafu(display("Failed to read file {}", path.display()))]
 	UnableToReadFile { source: std::io::Error, path: PathBuf },
 }
 
+async fn handle_list_directory(
+	req: ListDirectoryRequest,
+	project_dir: PathBuf,
+) -> Result<ListDirectoryResponse, ListDirectoryError> {
+	use list_directory_error::*;
+
+	let path =
+		project_path(req.path.clone(), &project_dir, true).await.context(InvalidPathSnafu)?;
+
+	let entries = walk_directory(&path, &req.path, req.recursive)
+		.await
+		.context(UnableToListDirectorySnafu { path })?;
+
+	Ok(ListDirectoryResponse(entries))
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum ListDirectoryError {
+	#[snafu(display("The directory path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
+	#[snafu(display("Failed to list directory {}", path.display()))]
+	UnableToListDirectory { source: std::io::Error, path: PathBuf },
+}
+
+async fn handle_stat(req: StatRequest, project_dir: PathBuf) -> Result<StatResponse, StatError> {
+	use stat_error::*;
+
+	let path = project_path(req.path, &project_dir, false).await.context(InvalidPathSnafu)?;
+
+	let metadata = match fs::symlink_metadata(&path).await {
+		Ok(metadata) => Ok(Some(file_metadata(&metadata))),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+		Err(e) => Err(e),
+	};
+
+	let metadata = metadata.context(UnableToStatSnafu { path })?;
+	Ok(StatResponse(metadata))
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum StatError {
+	#[snafu(display("The path is not valid"))]
+	InvalidPath { source: ProjectPathError },
+
+	#[snafu(display("Failed to get information about {}", path.display()))]
+	UnableToStat { source: std::io::Error, path: PathBuf },
+}
+
+// Every file operation goes through here. The path is resolved like
+// `parse_working_dir` does, and then may not lead out of the project
+// through a symlink. The last component is only followed when
+// `follow_last` is set, as deleting or inspecting a symlink doesn't
+// touch what it points to.
+async fn project_path(
+	path: String,
+	project_dir: &Path,
+	follow_last: bool,
+) -> Result<PathBuf, ProjectPathError> {
+	use project_path_error::*;
+
+	let path = parse_working_dir(Some(path), project_dir)?;
+
+	let checked = match path.parent() {
+		Some(parent) if !follow_last && path != project_dir => parent,
+		_ => &path,
+	};
+	let inside = is_inside_project(checked, project_dir)
+		.await
+		.context(UnableToResolveSnafu { path: checked })?;
+	ensure!(inside, OutsideProjectSnafu { path });
+
+	Ok(path)
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum ProjectPathError {
+	#[snafu(transparent)]
+	Parse { source: ParseWorkingDirError },
+
+	#[snafu(display("{} links to outside of the project", path.display()))]
+	OutsideProject { path: PathBuf },
+
+	#[snafu(display("Failed to resolve the symlinks of {}", path.display()))]
+	UnableToResolve { source: std::io::Error, path: PathBuf },
+}
+
+// `parse_working_dir` only looks at the path itself, but a symlink along
+// it could still point out of the project. A path that doesn't exist yet
+// is decided by the closest part of it that does.
+async fn is_inside_project(path: &Path, project_dir: &Path) -> io::Result<bool> {
+	let project_dir = fs::canonicalize(project_dir).await?;
+
+	for ancestor in path.ancestors() {
+		match fs::canonicalize(ancestor).await {
+			Ok(ancestor) => return Ok(ancestor.starts_with(&project_dir)),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {
+				// A dangling symlink could be pointing anywhere
+				if fs::symlink_metadata(ancestor).await.is_ok() {
+					return Ok(false);
+				}
+			}
+			Err(e) => return Err(e),
+		}
+	}
+
+	Ok(false)
+}
+
+/// Lists the entries of `dir`, sorted by path. The returned paths are
+/// `relative_dir` joined with the path of each entry inside `dir`.
+/// Symlinks are listed but never followed.
+async fn walk_directory(
+	dir: &Path,
+	relative_dir: &str,
+	recursive: bool,
+) -> io::Result<Vec<DirectoryEntry>> {
+	let mut entries = Vec::new();
+	let mut to_visit = vec![(dir.to_owned(), PathBuf::from(relative_dir))];
+
+	while let Some((dir, relative_dir)) = to_visit.pop() {
+		let mut read_dir = fs::read_dir(&dir).await?;
+
+		while let Some(entry) = read_dir.next_entry().await? {
+			let relative_path = relative_dir.join(entry.file_name());
+			let metadata = file_metadata(&entry.metadata().await?);
+
+			if recursive && metadata.kind == FileKind::Directory {
+				to_visit.push((entry.path(), relative_path.clone()));
+			}
+
+			let path = relative_path.to_string_lossy().into_owned();
+			entries.push(DirectoryEntry { path, metadata });
+		}
+	}
+
+	entries.sort_by(|a, b| a.path.cmp(&b.path));
+	Ok(entries)
+}
+
+fn file_metadata(metadata: &std::fs::Metadata) -> FileMetadata {
+	use std::time::UNIX_EPOCH;
+
+	let kind = if metadata.is_symlink() {
+		FileKind::Symlink
+	} else if metadata.is_dir() {
+		FileKind::Directory
+	} else {
+		FileKind::File
+	};
+
+	let modified_unix_secs = metadata
+		.modified()
+		.ok()
+		.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+		.map(|d| d.as_secs());
+
+	FileMetadata { kind, size_bytes: metadata.len(), modified_unix_secs }
+}
+
 // Current working directory defaults to project dir unless specified otherwise.
-fn parse_working_dir(cwd: Option<String>, project_path: impl Into<PathBuf>) -> PathBuf {
+// The path is resolved lexically and may not leave the project dir.
+fn parse_working_dir(
+	cwd: Option<String>,
+	project_path: impl Into<PathBuf>,
+) -> Result<PathBuf, ParseWorkingDirError> {
+	use std::path::Component;
+
+	use parse_working_dir_error::*;
+
 	let mut final_path = project_path.into();
-	if let Some(path) = cwd {
-		// Absolute path will replace final_path.
-		final_path.push(path)
+	let Some(path) = cwd else { return Ok(final_path) };
+
+	let mut depth = 0_usize;
+	for component in Path::new(&path).components() {
+		match component {
+			Component::Normal(c) => {
+				final_path.push(c);
+				depth += 1;
+			}
+			Component::CurDir => {}
+			Component::ParentDir => {
+				ensure!(depth > 0, OutsideProjectSnafu { path: &path });
+				final_path.pop();
+				depth -= 1;
+			}
+			Component::RootDir | Component::Prefix(_) => {
+				return AbsolutePathSnafu { path }.fail();
+			}
+		}
 	}
-	final_path
+
+	Ok(final_path)
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum ParseWorkingDirError {
+	#[snafu(display("The path {path} is absolute; it must be relative to the project"))]
+	AbsolutePath { path: String },
+
+	#[snafu(display("The path {path} points outside of the project"))]
+	OutsideProject { path: String },
 }
 
 enum ProcessCommand {
 	Start(ExecuteCommandRequest, MultiplexingSender),
-	Stdin(String),
+	Stdin(Vec<u8>),
 	StdinClose,
 	Kill,
 }
@@ -372,7 +907,7 @@ enum ProcessCommand {
 struct ProcessState {
 	project_path: PathBuf,
 	processes: JoinSet<Result<(), ProcessError>>,
-	stdin_senders: HashMap<JobId, mpsc::Sender<String>>,
+	stdin_senders: HashMap<JobId, mpsc::Sender<Vec<u8>>>,
 	stdin_shutdown_tx: mpsc::Sender<JobId>,
 	kill_tokens: HashMap<JobId, CancellationToken>,
 }
@@ -398,6 +933,10 @@ impl ProcessState {
 
 		let token = CancellationToken::new();
 
+		let statistics_interval = statistics_interval(req.statistics_interval);
+
+		let output_limit = req.output_limit.unwrap_or_default();
+
 		let RunningChild { child, stdin_rx, stdin, stdout, stderr } =
 			match process_begin(req, &self.project_path, &mut self.stdin_senders, job_id) {
 				Ok(v) => v,
@@ -416,10 +955,11 @@ impl ProcessState {
 		let statistics_task = tokio::task::spawn_blocking({
 			let child_id = child.id();
 			let worker_msg_tx = worker_msg_tx.clone();
-			move || stream_command_statistics(child_id, worker_msg_tx)
+			move || stream_command_statistics(child_id, statistics_interval, worker_msg_tx)
 		});
 
-		let task_set = stream_stdio(worker_msg_tx.clone(), stdin_rx, stdin, stdout, stderr);
+		let task_set =
+			stream_stdio(worker_msg_tx.clone(), output_limit, stdin_rx, stdin, stdout, stderr);
 
 		self.kill_tokens.insert(job_id, token.clone());
 
@@ -446,7 +986,7 @@ impl ProcessState {
 		Ok(())
 	}
 
-	async fn stdin(&mut self, job_id: JobId, packet: String) -> Result<(), ProcessError> {
+	async fn stdin(&mut self, job_id: JobId, packet: Vec<u8>) -> Result<(), ProcessError> {
 		use process_error::*;
 
 		if let Some(stdin_tx) = self.stdin_senders.get(&job_id) {
@@ -516,7 +1056,7 @@ async fn manage_processes(
 
 struct RunningChild {
 	child: Child,
-	stdin_rx: mpsc::Receiver<String>,
+	stdin_rx: mpsc::Receiver<Vec<u8>>,
 	stdin: ChildStdin,
 	stdout: ChildStdout,
 	stderr: ChildStderr,
@@ -525,16 +1065,16 @@ struct RunningChild {
 fn process_begin(
 	req: ExecuteCommandRequest,
 	project_path: &Path,
-	stdin_senders: &mut HashMap<JobId, mpsc::Sender<String>>,
+	stdin_senders: &mut HashMap<JobId, mpsc::Sender<Vec<u8>>>,
 	job_id: JobId,
 ) -> Result<RunningChild, ProcessError> {
 	use process_error::*;
 
-	let ExecuteCommandRequest { cmd, args, envs, cwd } = req;
+	let ExecuteCommandRequest { cmd, args, envs, cwd, .. } = req;
 	let mut child = Command::new(&cmd)
 		.args(args)
 		.envs(envs)
-		.current_dir(parse_working_dir(cwd, project_path))
+		.current_dir(parse_working_dir(cwd, project_path).context(InvalidWorkingDirectorySnafu)?)
 		.kill_on_drop(true)
 		.stdin(Stdio::piped())
 		.stdout(Stdio::piped())
@@ -557,7 +1097,9 @@ async fn process_end(
 	token: CancellationToken,
 	mut child: Child,
 	mut task_set: JoinSet<Result<(), StdioError>>,
-	statistics_task: tokio::task::JoinHandle<Result<(), CommandStatisticsError>>,
+	statistics_task: tokio::task::JoinHandle<
+		Result<Option<CommandStatistics>, CommandStatisticsError>,
+	>,
 	stdin_shutdown_tx: mpsc::Sender<JobId>,
 	job_id: JobId,
 ) -> Result<ExecuteCommandResponse, ProcessError> {
@@ -599,7 +1141,7 @@ async fn process_end(
 	}
 
 	// TODO: check this for death earlier?
-	statistics_task
+	let statistics = statistics_task
 		.await
 		.context(StatisticsTaskPanickedSnafu)?
 		.context(StatisticsTaskFailedSnafu)?;
@@ -607,7 +1149,7 @@ async fn process_end(
 	let success = status.success();
 	let exit_detail = extract_exit_detail(status);
 
-	Ok(ExecuteCommandResponse { success, exit_detail })
+	Ok(ExecuteCommandResponse { success, exit_detail, statistics })
 }
 
 mod signals {
@@ -712,6 +1254,9 @@ fn extract_exit_detail(status: ExitStatus) -> String {
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum ProcessError {
+	#[snafu(display("The working directory is not valid"))]
+	InvalidWorkingDirectory { source: ParseWorkingDirError },
+
 	#[snafu(display("Failed to spawn child process {cmd}"))]
 	UnableToSpawnProcess { source: std::io::Error, cmd: String },
 
@@ -782,13 +1327,24 @@ mod stats {
 			Ok(Self { pid, timebase })
 		}
 
+		// Only covers the process itself, not its descendants.
 		pub fn stats(&self) -> Option<CommandStatistics> {
 			let usage = proc_pid_rusage(self.pid).ok()?;
 
-			let total_time_secs = self.ticks_to_seconds(usage.ri_user_time + usage.ri_system_time);
+			let user_time_secs = self.ticks_to_seconds(usage.ri_user_time);
+			let system_time_secs = self.ticks_to_seconds(usage.ri_system_time);
 			let resident_set_size_bytes = usage.ri_resident_size;
 
-			Some(CommandStatistics { total_time_secs, resident_set_size_bytes })
+			Some(CommandStatistics {
+				total_time_secs: user_time_secs + system_time_secs,
+				user_time_secs,
+				system_time_secs,
+				resident_set_size_bytes,
+				peak_resident_set_size_bytes: resident_set_size_bytes,
+				read_bytes: Some(usage.ri_diskio_bytesread),
+				written_bytes: Some(usage.ri_diskio_byteswritten),
+				..Default::default()
+			})
 		}
 
 		fn ticks_to_seconds(&self, v: u64) -> f64 {
@@ -833,34 +1389,135 @@ mod stats {
 
 #[cfg(target_os = "linux")]
 mod stats {
-	use procfs::process::Process as ProcfsProcess;
+	use std::path::PathBuf;
+
+	use procfs::process::{Process as ProcfsProcess, Stat};
 	use snafu::prelude::*;
 
 	use crate::message::CommandStatistics;
 
 	pub struct Process {
-		process: ProcfsProcess,
+		pid: i32,
+		proc_root: PathBuf,
+		// Kernels without `CONFIG_PROC_CHILDREN` don't have them
+		has_children_files: bool,
 		ticks_per_second: u64,
 		page_size: u64,
 	}
 
 	impl Process {
-		pub fn new(pid: i32) -> Result<Self, Error> {
-			let process = ProcfsProcess::new(pid).context(Snafu)?;
+		pub fn new(pid: i32) -> Result<Self, Error> { Self::with_proc_root(pid, "/proc".into()) }
+
+		pub fn with_proc_root(pid: i32, proc_root: PathBuf) -> Result<Self, Error> {
+			let process =
+				ProcfsProcess::new_with_root(proc_root.join(pid.to_string())).context(Snafu)?;
+			let has_children_files =
+				process.task_main_thread().and_then(|task| task.children()).is_ok();
 
 			let ticks_per_second = procfs::ticks_per_second();
 			let page_size = procfs::page_size();
 
-			Ok(Self { process, ticks_per_second, page_size })
+			Ok(Self { pid, proc_root, has_children_files, ticks_per_second, page_size })
 		}
 
+		// `cargo run` is our child; the program we care about is one of
+		// its descendants.
 		pub fn stats(&self) -> Option<CommandStatistics> {
-			let stat = self.process.stat().ok()?;
+			let processes = self.process_tree()?;
+
+			let mut user_ticks = 0;
+			let mut system_ticks = 0;
+			let mut rss_pages = 0;
+			let mut threads = 0;
+			let mut voluntary_context_switches = 0;
+			let mut involuntary_context_switches = 0;
+			let mut read_bytes = 0;
+			let mut written_bytes = 0;
+
+			for (process, stat) in &processes {
+				// Reaped children are accounted to their parent.
+				user_ticks += stat.utime + u64::try_from(stat.cutime).unwrap_or(0);
+				system_ticks += stat.stime + u64::try_from(stat.cstime).unwrap_or(0);
+				rss_pages += stat.rss;
+				threads += u64::try_from(stat.num_threads).unwrap_or(0);
+
+				if let Ok(status) = process.status() {
+					voluntary_context_switches += status.voluntary_ctxt_switches.unwrap_or(0);
+					involuntary_context_switches += status.nonvoluntary_ctxt_switches.unwrap_or(0);
+				}
+
+				if let Ok(io) = process.io() {
+					read_bytes += io.read_bytes;
+					written_bytes += io.write_bytes;
+				}
+			}
+
+			let user_time_secs = self.ticks_to_seconds(user_ticks);
+			let system_time_secs = self.ticks_to_seconds(system_ticks);
+			let resident_set_size_bytes = self.pages_to_bytes(rss_pages);
+
+			Some(CommandStatistics {
+				total_time_secs: user_time_secs + system_time_secs,
+				user_time_secs,
+				system_time_secs,
+				resident_set_size_bytes,
+				// The peak across samples is tracked by the caller
+				peak_resident_set_size_bytes: resident_set_size_bytes,
+				voluntary_context_switches: Some(voluntary_context_switches),
+				involuntary_context_switches: Some(involuntary_context_switches),
+				read_bytes: Some(read_bytes),
+				written_bytes: Some(written_bytes),
+				threads: Some(threads),
+			})
+		}
+
+		// Returns `None` once our child is gone.
+		fn process_tree(&self) -> Option<Vec<(ProcfsProcess, Stat)>> {
+			if !self.has_children_files {
+				return self.scan_process_tree();
+			}
+
+			let mut processes = vec![self.process(self.pid)?];
+			let mut i = 0;
+			while let Some((process, _)) = processes.get(i) {
+				let children = child_pids(process);
+				processes.extend(children.into_iter().filter_map(|pid| self.process(pid)));
+				i += 1;
+			}
+
+			Some(processes)
+		}
+
+		fn process(&self, pid: i32) -> Option<(ProcfsProcess, Stat)> {
+			let process =
+				ProcfsProcess::new_with_root(self.proc_root.join(pid.to_string())).ok()?;
+			let stat = process.stat().ok()?;
+			Some((process, stat))
+		}
+
+		// Reads every process to find the children of each one
+		fn scan_process_tree(&self) -> Option<Vec<(ProcfsProcess, Stat)>> {
+			let mut processes = procfs::process::all_processes_with_root(&self.proc_root)
+				.ok()?
+				.filter_map(|p| {
+					let p = p.ok()?;
+					let stat = p.stat().ok()?;
+					Some((p, stat))
+				})
+				.collect::<Vec<_>>();
+
+			let mut tree = vec![self.pid];
+			let mut i = 0;
+			while let Some(&pid) = tree.get(i) {
+				let children = processes.iter().filter(|(_, s)| s.ppid == pid).map(|(_, s)| s.pid);
+				tree.extend(children);
+				i += 1;
+			}
 
-			let total_time_secs = self.ticks_to_seconds(stat.utime + stat.stime);
-			let resident_set_size_bytes = self.pages_to_bytes(stat.rss);
+			processes.retain(|(_, s)| tree.contains(&s.pid));
 
-			Some(CommandStatistics { total_time_secs, resident_set_size_bytes })
+			let has_root = processes.iter().any(|(_, s)| s.pid == self.pid);
+			has_root.then_some(processes)
 		}
 
 		fn ticks_to_seconds(&self, v: u64) -> f64 { v as f64 / self.ticks_per_second as f64 }
@@ -868,6 +1525,17 @@ mod stats {
 		fn pages_to_bytes(&self, v: u64) -> u64 { v * self.page_size }
 	}
 
+	// Any thread of a process can be the parent of a child
+	fn child_pids(process: &ProcfsProcess) -> Vec<i32> {
+		let Ok(tasks) = process.tasks() else { return Vec::new() };
+
+		tasks
+			.flatten()
+			.flat_map(|task| task.children().unwrap_or_default())
+			.filter_map(|pid| i32::try_from(pid).ok())
+			.collect()
+	}
+
 	#[derive(Debug, Snafu)]
 	#[snafu(display("Could not get information for the process"))]
 	pub struct Error {
@@ -875,34 +1543,47 @@ mod stats {
 	}
 }
 
+const DEFAULT_STATISTICS_INTERVAL: Duration = Duration::from_secs(1);
+const MINIMUM_STATISTICS_INTERVAL: Duration = Duration::from_millis(100);
+
+fn statistics_interval(requested: Option<Duration>) -> Duration {
+	requested.unwrap_or(DEFAULT_STATISTICS_INTERVAL).max(MINIMUM_STATISTICS_INTERVAL)
+}
+
+// Returns the last statistics sampled, which serve as a summary.
 fn stream_command_statistics(
 	child_id: Option<u32>,
+	interval: Duration,
 	worker_msg_tx: MultiplexingSender,
-) -> Result<(), CommandStatisticsError> {
-	use std::time::Duration;
-
+) -> Result<Option<CommandStatistics>, CommandStatisticsError> {
 	use command_statistics_error::*;
 	use stats::*;
 
-	const STATISTIC_INTERVAL: Duration = Duration::from_secs(1);
-
 	let process_id = child_id.context(ChildIdMissingSnafu)?;
 
 	let process_id = process_id.try_into().context(ProcessIdOutOfRangeSnafu { process_id })?;
 
 	let process = Process::new(process_id).context(InvalidProcessSnafu { process_id })?;
 
-	while let Some(stats) = process.stats() {
+	let mut summary: Option<CommandStatistics> = None;
+
+	while let Some(mut stats) = process.stats() {
+		if let Some(previous) = &summary {
+			stats.peak_resident_set_size_bytes =
+				stats.peak_resident_set_size_bytes.max(previous.peak_resident_set_size_bytes);
+		}
+		summary = Some(stats.clone());
+
 		let sent = futures::executor::block_on(worker_msg_tx.send_ok(stats));
 		if sent.is_err() {
 			// No one listening anymore
 			break;
 		}
 
-		std::thread::sleep(STATISTIC_INTERVAL);
+		std::thread::sleep(interval);
 	}
 
-	Ok(())
+	Ok(summary)
 }
 
 #[derive(Debug, Snafu)]
@@ -920,7 +1601,8 @@ pub enum CommandStatisticsError {
 
 fn stream_stdio(
 	coordinator_tx: MultiplexingSender,
-	mut stdin_rx: mpsc::Receiver<String>,
+	output_limit: OutputLimit,
+	mut stdin_rx: mpsc::Receiver<Vec<u8>>,
 	mut stdin: ChildStdin,
 	stdout: ChildStdout,
 	stderr: ChildStderr,
@@ -931,7 +1613,7 @@ fn stream_stdio(
 
 	set.spawn(async move {
 		while let Some(data) = stdin_rx.recv().await {
-			stdin.write_all(data.as_bytes()).await.context(UnableToWriteStdinSnafu)?;
+			stdin.write_all(&data).await.context(UnableToWriteStdinSnafu)?;
 			stdin.flush().await.context(UnableToFlushStdinSnafu)?;
 		}
 
@@ -939,12 +1621,12 @@ fn stream_stdio(
 	});
 
 	set.spawn({
-		copy_child_output(stdout, coordinator_tx.clone(), WorkerMessage::StdoutPacket)
+		copy_child_output(stdout, coordinator_tx.clone(), output_limit, OutputChunk::into_stdout)
 			.context(CopyStdoutSnafu)
 	});
 
 	set.spawn({
-		copy_child_output(stderr, coordinator_tx, WorkerMessage::StderrPacket)
+		copy_child_output(stderr, coordinator_tx, output_limit, OutputChunk::into_stderr)
 			.context(CopyStderrSnafu)
 	});
 
@@ -967,10 +1649,53 @@ pub enum StdioError {
 	CopyStderr { source: CopyChildOutputError },
 }
 
+impl OutputChunk {
+	fn len(&self) -> usize {
+		match self {
+			OutputChunk::Text(s) => s.len(),
+			OutputChunk::Bytes(b) => b.len(),
+		}
+	}
+
+	fn is_empty(&self) -> bool { self.len() == 0 }
+
+	/// Like [`Vec::split_off`], but text is only split on a character
+	/// boundary, moving the split point earlier if needed.
+	fn split_off(&mut self, at: usize) -> Self {
+		let at = at.min(self.len());
+
+		match self {
+			OutputChunk::Text(s) => {
+				let mut at = at;
+				while !s.is_char_boundary(at) {
+					at -= 1;
+				}
+				OutputChunk::Text(s.split_off(at))
+			}
+			OutputChunk::Bytes(b) => OutputChunk::Bytes(b.split_off(at)),
+		}
+	}
+
+	fn into_stdout(self) -> WorkerMessage {
+		match self {
+			OutputChunk::Text(s) => WorkerMessage::StdoutPacket(s),
+			OutputChunk::Bytes(b) => WorkerMessage::StdoutBytes(b),
+		}
+	}
+
+	fn into_stderr(self) -> WorkerMessage {
+		match self {
+			OutputChunk::Text(s) => WorkerMessage::StderrPacket(s),
+			OutputChunk::Bytes(b) => WorkerMessage::StderrBytes(b),
+		}
+	}
+}
+
 struct Utf8BufReader<R> {
 	reader: R,
 	buffer: Box<[u8]>,
-	n_incomplete: usize,
+	n_buffered: usize,
+	input_closed: bool,
 }
 
 impl<R> Utf8BufReader<R>
@@ -980,69 +1705,100 @@ where
 	const DEFAULT_CAPACITY: usize = 32 * 1024;
 
 	fn new(reader: R) -> Self {
-		Self { reader, buffer: vec![0; Self::DEFAULT_CAPACITY].into(), n_incomplete: 0 }
+		Self {
+			reader,
+			buffer: vec![0; Self::DEFAULT_CAPACITY].into(),
+			n_buffered: 0,
+			input_closed: false,
+		}
 	}
 
-	async fn next(&mut self) -> Result<Option<String>, Utf8BufReaderError> {
-		use std::str;
-
+	async fn next(&mut self) -> Result<Option<OutputChunk>, Utf8BufReaderError> {
 		use utf8_buf_reader_error::*;
 
 		loop {
-			let after_incomplete_bytes = &mut self.buffer[self.n_incomplete..];
-			let n_read = self.reader.read(after_incomplete_bytes).await.context(ReaderSnafu)?;
-			let n_valid = self.n_incomplete + n_read;
+			// Data left over from a previous read may already be complete.
+			if let Some(chunk) = self.take_chunk() {
+				return Ok(Some(chunk));
+			}
 
-			if n_read == 0 && self.n_incomplete == 0 {
+			if self.input_closed {
 				return Ok(None);
 			}
 
-			let valid_utf_8_bytes = match str::from_utf8(&self.buffer[..n_valid]) {
-				Ok(s) => s.len(),
-				Err(e) => e.valid_up_to(),
-			};
+			let after_buffered_bytes = &mut self.buffer[self.n_buffered..];
+			let n_read = self.reader.read(after_buffered_bytes).await.context(ReaderSnafu)?;
 
-			// We can't parse any UTF-8
-			if valid_utf_8_bytes == 0 {
-				// This should be enough bytes to get one UTF-8 character.
-				ensure!(n_valid < 4, InvalidUtf8Snafu);
+			self.n_buffered += n_read;
+			self.input_closed = n_read == 0;
+		}
+	}
 
-				// We aren't going to get any more input
-				ensure!(n_read != 0, RanOutOfInputSnafu);
-			}
+	fn take_chunk(&mut self) -> Option<OutputChunk> {
+		use std::str;
+
+		let bytes = &self.buffer[..self.n_buffered];
 
+		let valid_utf_8_bytes = match str::from_utf8(bytes) {
+			Ok(s) => s.len(),
+			Err(e) => e.valid_up_to(),
+		};
+
+		let (n_taken, chunk) = if valid_utf_8_bytes != 0 {
 			// Safety: We just calculated the number of valid UTF-8 bytes
 			// and the buffer hasn't changed since then.
 			let s = unsafe {
-				let utf8_bytes = self.buffer.get_unchecked(..valid_utf_8_bytes);
+				let utf8_bytes = bytes.get_unchecked(..valid_utf_8_bytes);
 				str::from_utf8_unchecked(utf8_bytes)
 			};
-			let s = s.to_owned();
 
-			// Move any trailing incomplete bytes
-			self.buffer.copy_within(valid_utf_8_bytes..n_valid, 0);
+			(valid_utf_8_bytes, OutputChunk::Text(s.to_owned()))
+		} else {
+			// We can't parse any UTF-8
+			let n_invalid = invalid_utf8_prefix_len(bytes, self.input_closed);
+			if n_invalid == 0 {
+				return None;
+			}
+
+			(n_invalid, OutputChunk::Bytes(bytes[..n_invalid].to_vec()))
+		};
+
+		// Move any trailing bytes
+		self.buffer.copy_within(n_taken..self.n_buffered, 0);
+		self.n_buffered -= n_taken;
+
+		Some(chunk)
+	}
+}
 
-			self.n_incomplete = n_valid - valid_utf_8_bytes;
+// The number of leading bytes that can never become valid UTF-8. An
+// incomplete character only counts once no more input will arrive.
+fn invalid_utf8_prefix_len(bytes: &[u8], at_end: bool) -> usize {
+	let mut n_invalid = 0;
+
+	while let Err(e) = std::str::from_utf8(&bytes[n_invalid..]) {
+		if e.valid_up_to() != 0 {
+			break;
+		}
 
-			if !s.is_empty() {
-				return Ok(Some(s));
+		match e.error_len() {
+			Some(n) => n_invalid += n,
+			None => {
+				if at_end {
+					n_invalid = bytes.len();
+				}
+				break;
 			}
 		}
 	}
+
+	n_invalid
 }
 
 #[derive(Debug, Snafu)]
 #[snafu(module)]
 pub enum Utf8BufReaderError {
-	Reader {
-		source: std::io::Error,
-	},
-
-	#[snafu(display("Insufficient data to complete a UTF-8 character"))]
-	RanOutOfInput,
-
-	#[snafu(display("Found non-UTF-8 data"))]
-	InvalidUtf8,
+	Reader { source: std::io::Error },
 }
 
 #[cfg(test)]
@@ -1083,6 +1839,8 @@ mod test {
 		}
 	}
 
+	fn text(s: &str) -> OutputChunk { OutputChunk::Text(s.into()) }
+
 	#[tokio::test]
 	async fn small_reads() {
 		let bytes: [u8; 4] = "🙂".as_bytes().try_into().unwrap();
@@ -1090,8 +1848,8 @@ mod test {
 		let reader = FixedAsyncRead::success_exact(bytes.map(|b| [b]));
 		let mut buffer = Utf8BufReader::new(reader);
 
-		assert_eq!(buffer.next().await.unwrap().as_deref(), Some("🙂"));
-		assert_eq!(buffer.next().await.unwrap().as_deref(), None);
+		assert_eq!(buffer.next().await.unwrap(), Some(text("🙂")));
+		assert_eq!(buffer.next().await.unwrap(), None);
 		assert!(buffer.reader.is_empty());
 	}
 
@@ -1103,7 +1861,8 @@ mod test {
 		let reader = FixedAsyncRead::success_exact([partial_string]);
 		let mut buffer = Utf8BufReader::new(reader);
 
-		assert_matches!(buffer.next().await, Err(Utf8BufReaderError::RanOutOfInput));
+		assert_eq!(buffer.next().await.unwrap(), Some(OutputChunk::Bytes(partial_string.into())));
+		assert_eq!(buffer.next().await.unwrap(), None);
 		assert!(buffer.reader.is_empty());
 	}
 
@@ -1115,7 +1874,7 @@ mod test {
 		let reader = FixedAsyncRead::success_exact([bytes]);
 		let mut buffer = Utf8BufReader::new(reader);
 
-		assert_matches!(buffer.next().await, Err(Utf8BufReaderError::InvalidUtf8));
+		assert_eq!(buffer.next().await.unwrap(), Some(OutputChunk::Bytes(bytes.into())));
 		assert!(!buffer.reader.is_empty());
 	}
 
@@ -1126,8 +1885,12 @@ mod test {
 		let reader = FixedAsyncRead::success_exact([bytes]);
 		let mut buffer = Utf8BufReader::new(reader);
 
-		assert_matches!(buffer.next().await, Ok(Some(s)) => s == "A");
-		assert_matches!(buffer.next().await, Err(Utf8BufReaderError::InvalidUtf8));
+		assert_eq!(buffer.next().await.unwrap(), Some(text("A")));
+		for _ in 0..3 {
+			assert_eq!(buffer.next().await.unwrap(), Some(OutputChunk::Bytes(vec![0xc3])));
+			assert_eq!(buffer.next().await.unwrap(), Some(text("(")));
+		}
+		assert_eq!(buffer.next().await.unwrap(), None);
 		assert!(buffer.reader.is_empty());
 	}
 
@@ -1139,38 +1902,566 @@ mod test {
 		let reader = FixedAsyncRead::success_exact([head, tail]);
 		let mut buffer = Utf8BufReader::new(reader);
 
-		assert_eq!(buffer.next().await.unwrap().as_deref(), Some("🙂"));
-		assert_eq!(buffer.next().await.unwrap().as_deref(), Some("🙂🙂"));
-		assert_eq!(buffer.next().await.unwrap().as_deref(), None);
+		assert_eq!(buffer.next().await.unwrap(), Some(text("🙂")));
+		assert_eq!(buffer.next().await.unwrap(), Some(text("🙂🙂")));
+		assert_eq!(buffer.next().await.unwrap(), None);
 		assert!(buffer.reader.is_empty());
 	}
-}
 
-const OUTPUT_BYTE_LIMIT: usize = 640 * 1024;
+	#[tokio::test]
+	async fn head_and_tail_within_limit() {
+		let mut output = HeadAndTail::new(8);
+
+		assert_eq!(output.push(text("abc")), text("abc"));
+		assert_eq!(output.push(text("defg")), text("d"));
+		assert_eq!(output.push(text("h")), text(""));
+		assert_eq!(output.finish().await.unwrap(), [text("efgh")]);
+	}
+
+	#[tokio::test]
+	async fn head_and_tail_omits_middle() {
+		let mut output = HeadAndTail::new(8);
+
+		assert_eq!(output.push(text("ab🙂")), text("ab"));
+		assert_eq!(output.push(text("0123456789")), text(""));
+		assert_eq!(
+			output.finish().await.unwrap(),
+			[text("\n[... 10 bytes of output omitted ...]\n"), text("6789")]
+		);
+	}
+
+	#[tokio::test]
+	async fn head_and_tail_does_not_split_characters() {
+		let mut output = HeadAndTail::new(8);
+
+		assert_eq!(output.push(text("abcd")), text("abcd"));
+		assert_eq!(output.push(text("x🙂yz")), text(""));
+		assert_eq!(
+			output.finish().await.unwrap(),
+			[text("\n[... 5 bytes of output omitted ...]\n"), text("yz")]
+		);
+	}
+
+	#[test]
+	fn hello_is_decodable_by_any_protocol_version() {
+		let variant_index = |bytes: Vec<u8>| u32::from_le_bytes(bytes[..4].try_into().unwrap());
+
+		let hello = crate::message::HelloRequest { protocol_version: PROTOCOL_VERSION };
+		let hello = bincode::serialize(&CoordinatorMessage::Hello(hello)).unwrap();
+		assert_eq!(variant_index(hello), 0);
+
+		let hello = hello_response(Path::new("/playground"));
+		let hello = bincode::serialize(&WorkerMessage::Hello(hello)).unwrap();
+		assert_eq!(variant_index(hello), 0);
+	}
+
+	#[test]
+	fn capabilities_of_newer_workers_are_decodable() {
+		#[derive(serde::Serialize)]
+		#[allow(dead_code)]
+		enum NewerCapability {
+			CreateProject,
+			FilesystemOperations,
+			ConfinedPaths,
+			WasmTest,
+			MiriFlags,
+			StdinBytes,
+			Unknown,
+			FromTheFuture,
+		}
+
+		let capabilities = vec![NewerCapability::StdinBytes, NewerCapability::FromTheFuture];
+		let capabilities = bincode::serialize(&capabilities).unwrap();
+		let capabilities: Vec<Capability> = bincode::deserialize(&capabilities).unwrap();
+
+		assert_eq!(capabilities, [Capability::StdinBytes, Capability::Unknown]);
+		assert!(!CAPABILITIES.contains(&Capability::Unknown));
+	}
+
+	#[test]
+	fn working_dir_inside_project() {
+		let project_dir = Path::new("/playground");
+		let parse = |path: &str| parse_working_dir(Some(path.into()), project_dir);
+
+		assert_eq!(parse_working_dir(None, project_dir).unwrap(), project_dir);
+		assert_eq!(parse(".").unwrap(), project_dir);
+		assert_eq!(parse("src/main.rs").unwrap(), project_dir.join("src/main.rs"));
+		assert_eq!(parse("./src/../pkg/").unwrap(), project_dir.join("pkg"));
+	}
+
+	#[test]
+	fn working_dir_outside_project_rejected() {
+		let project_dir = Path::new("/playground");
+		let parse = |path: &str| parse_working_dir(Some(path.into()), project_dir);
+
+		assert_matches!(parse("/etc/passwd"), Err(ParseWorkingDirError::AbsolutePath { .. }));
+		assert_matches!(parse(".."), Err(ParseWorkingDirError::OutsideProject { .. }));
+		assert_matches!(parse("src/../../etc"), Err(ParseWorkingDirError::OutsideProject { .. }));
+	}
+
+	#[tokio::test]
+	async fn file_requests_outside_project_rejected() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		let outside = project_dir.path().parent().unwrap().join("outside.rs");
+
+		for path in [outside.to_string_lossy().into_owned(), "../outside.rs".into()] {
+			let req = WriteFileRequest { path: path.clone(), content: "fn main() {}".into() };
+			let result = handle_write_file(req, project_dir.path().into()).await;
+			assert_matches!(result, Err(WriteFileError::InvalidPath { .. }));
+
+			let req = ReadFileRequest { path: path.clone() };
+			let result = handle_read_file(req, project_dir.path().into()).await;
+			assert_matches!(result, Err(ReadFileError::InvalidPath { .. }));
+
+			let req = DeleteFileRequest { path };
+			let result = handle_delete_file(req, project_dir.path().into()).await;
+			assert_matches!(result, Err(DeleteFileError::InvalidPath { .. }));
+		}
+
+		assert!(!outside.exists());
+	}
+
+	#[tokio::test]
+	async fn file_requests_through_symlinks_rejected() {
+		let outside = tempdir::TempDir::new("outside").unwrap();
+		std::fs::write(outside.path().join("secret"), "secret").unwrap();
+
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::os::unix::fs::symlink(outside.path(), project_dir.path().join("link")).unwrap();
+		std::os::unix::fs::symlink(outside.path().join("secret"), project_dir.path().join("file"))
+			.unwrap();
+		std::os::unix::fs::symlink(
+			outside.path().join("missing"),
+			project_dir.path().join("dangling"),
+		)
+		.unwrap();
+
+		for path in ["link/secret", "link/new", "file", "dangling"] {
+			let req = WriteFileRequest { path: path.into(), content: "changed".into() };
+			let result = handle_write_file(req, project_dir.path().into()).await;
+			assert_matches!(
+				result,
+				Err(WriteFileError::InvalidPath {
+					source: ProjectPathError::OutsideProject { .. }
+				}),
+				"{path}"
+			);
+
+			let req = ReadFileRequest { path: path.into() };
+			let result = handle_read_file(req, project_dir.path().into()).await;
+			assert_matches!(result, Err(ReadFileError::InvalidPath { .. }), "{path}");
+		}
+
+		let req = DeleteFileRequest { path: "link/secret".into() };
+		let result = handle_delete_file(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(DeleteFileError::InvalidPath { .. }));
+
+		let req = StatRequest { path: "link/secret".into() };
+		let result = handle_stat(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(StatError::InvalidPath { .. }));
+
+		// The link itself is inside the project
+		let req = DeleteFileRequest { path: "file".into() };
+		handle_delete_file(req, project_dir.path().into()).await.unwrap();
+
+		assert_eq!(std::fs::read_to_string(outside.path().join("secret")).unwrap(), "secret");
+		assert!(!outside.path().join("new").exists());
+		assert!(!outside.path().join("missing").exists());
+	}
+
+	#[tokio::test]
+	async fn create_project_from_template() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::fs::write(project_dir.path().join("Cargo.toml"), "[package]").unwrap();
+
+		let req = CreateProjectRequest { path: "packages/slide".into() };
+		handle_create_project(req, project_dir.path().into()).await.unwrap();
+
+		let project = project_dir.path().join("packages/slide");
+		let cargo_toml = std::fs::read_to_string(project.join("Cargo.toml")).unwrap();
+		let cargo_config = std::fs::read_to_string(project.join(".cargo/config.toml")).unwrap();
+
+		assert_eq!(cargo_toml, "[package]");
+		assert!(!project.join("Cargo.lock").exists());
+		assert!(cargo_config.contains(r#"target-dir = "../../target""#));
+	}
+
+	#[tokio::test]
+	async fn create_project_keeps_existing_files() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::fs::write(project_dir.path().join("Cargo.toml"), "[package]").unwrap();
+
+		let project = project_dir.path().join("slide");
+		std::fs::create_dir(&project).unwrap();
+		std::fs::write(project.join("Cargo.toml"), "[package] # modified").unwrap();
+
+		let req = CreateProjectRequest { path: "slide".into() };
+		handle_create_project(req, project_dir.path().into()).await.unwrap();
+
+		let cargo_toml = std::fs::read_to_string(project.join("Cargo.toml")).unwrap();
+		assert_eq!(cargo_toml, "[package] # modified");
+	}
+
+	#[tokio::test]
+	async fn list_directory_recursively() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::fs::create_dir_all(project_dir.path().join("pkg/snippets")).unwrap();
+		std::fs::write(project_dir.path().join("pkg/slide.js"), "js").unwrap();
+		std::fs::write(project_dir.path().join("pkg/snippets/inline.js"), "inline").unwrap();
+
+		let req = ListDirectoryRequest { path: "pkg".into(), recursive: true };
+		let ListDirectoryResponse(entries) =
+			handle_list_directory(req, project_dir.path().into()).await.unwrap();
+
+		let entries =
+			entries.iter().map(|e| (e.path.as_str(), e.metadata.kind)).collect::<Vec<_>>();
+		assert_eq!(
+			entries,
+			[
+				("pkg/slide.js", FileKind::File),
+				("pkg/snippets", FileKind::Directory),
+				("pkg/snippets/inline.js", FileKind::File),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn read_files_expands_directories() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		let files = ["Cargo.toml", "pkg/slide.js", "pkg/slide_bg.wasm"]
+			.map(|path| WriteFileRequest { path: path.into(), content: path.into() });
+
+		let req = WriteFilesRequest { files: files.into() };
+		handle_write_files(req, project_dir.path().into()).await.unwrap();
+
+		let req = ReadFilesRequest { paths: vec!["Cargo.toml".into(), "pkg".into()] };
+		let ReadFilesResponse(files) =
+			handle_read_files(req, project_dir.path().into()).await.unwrap();
+
+		for file in &files {
+			assert_eq!(file.content, file.path.as_bytes());
+		}
+		let paths = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+		assert_eq!(paths, ["Cargo.toml", "pkg/slide.js", "pkg/slide_bg.wasm"]);
+	}
+
+	#[tokio::test]
+	async fn symlinks_out_of_project_not_followed() {
+		let outside = tempdir::TempDir::new("outside").unwrap();
+		std::fs::write(outside.path().join("secret"), "secret").unwrap();
+
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::fs::create_dir(project_dir.path().join("pkg")).unwrap();
+		std::fs::write(project_dir.path().join("pkg/slide.js"), "js").unwrap();
+		std::os::unix::fs::symlink(outside.path(), project_dir.path().join("pkg/dir")).unwrap();
+		std::os::unix::fs::symlink(
+			outside.path().join("secret"),
+			project_dir.path().join("pkg/file"),
+		)
+		.unwrap();
+		std::os::unix::fs::symlink(outside.path(), project_dir.path().join("link")).unwrap();
+
+		let req = ReadFilesRequest { paths: vec!["pkg".into()] };
+		let ReadFilesResponse(files) =
+			handle_read_files(req, project_dir.path().into()).await.unwrap();
+		let paths = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+		assert_eq!(paths, ["pkg/slide.js"]);
+
+		for path in ["pkg/file", "link", "link/secret"] {
+			let req = ReadFilesRequest { paths: vec![path.into()] };
+			let result = handle_read_files(req, project_dir.path().into()).await;
+			assert_matches!(
+				result,
+				Err(ReadFilesError::InvalidPath {
+					source: ProjectPathError::OutsideProject { .. }
+				}),
+				"{path}"
+			);
+		}
+
+		let req = ListDirectoryRequest { path: "link".into(), recursive: true };
+		let result = handle_list_directory(req, project_dir.path().into()).await;
+		assert_matches!(
+			result,
+			Err(ListDirectoryError::InvalidPath {
+				source: ProjectPathError::OutsideProject { .. }
+			})
+		);
+	}
+
+	#[tokio::test]
+	async fn changes_through_symlinks_rejected() {
+		let outside = tempdir::TempDir::new("outside").unwrap();
+		std::fs::create_dir(outside.path().join("pkg")).unwrap();
+		std::fs::write(outside.path().join("pkg/secret"), "secret").unwrap();
+
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::os::unix::fs::symlink(outside.path(), project_dir.path().join("link")).unwrap();
+
+		let files = ["src/lib.rs", "link/pkg/secret"]
+			.map(|path| WriteFileRequest { path: path.into(), content: "changed".into() });
+		let req = WriteFilesRequest { files: files.into() };
+		let result = handle_write_files(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(WriteFilesError::UnableToWrite { path, .. }) => {
+			assert_eq!(path, "link/pkg/secret");
+		});
+
+		let req = RemoveDirectoryRequest { path: "link/pkg".into() };
+		let result = handle_remove_directory(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(RemoveDirectoryError::InvalidPath { .. }));
+
+		let req = StatRequest { path: "link/pkg".into() };
+		let result = handle_stat(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(StatError::InvalidPath { .. }));
+
+		// Only the link is removed, not what it points to
+		let req = RemoveDirectoryRequest { path: "link".into() };
+		handle_remove_directory(req, project_dir.path().into()).await.unwrap();
+
+		assert!(!project_dir.path().join("link").exists());
+		assert_eq!(std::fs::read_to_string(outside.path().join("pkg/secret")).unwrap(), "secret");
+	}
+
+	#[tokio::test]
+	async fn remove_directory_and_stat() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+		std::fs::create_dir_all(project_dir.path().join("pkg/snippets")).unwrap();
+
+		let stat = |path: &str| {
+			let req = StatRequest { path: path.into() };
+			handle_stat(req, project_dir.path().into())
+		};
+		assert_matches!(stat("pkg").await, Ok(StatResponse(Some(m))) => {
+			assert_eq!(m.kind, FileKind::Directory);
+		});
+
+		let req = RemoveDirectoryRequest { path: "pkg".into() };
+		handle_remove_directory(req, project_dir.path().into()).await.unwrap();
+		assert_matches!(stat("pkg").await, Ok(StatResponse(None)));
+
+		let req = RemoveDirectoryRequest { path: ".".into() };
+		let result = handle_remove_directory(req, project_dir.path().into()).await;
+		assert_matches!(result, Err(RemoveDirectoryError::RemovingProjectDirectory));
+	}
+
+	#[tokio::test]
+	async fn create_project_outside_base_rejected() {
+		let project_dir = tempdir::TempDir::new("worker").unwrap();
+
+		for path in ["", "/tmp/slide", "../slide", "packages/../../slide"] {
+			let req = CreateProjectRequest { path: path.into() };
+			let result = handle_create_project(req, project_dir.path().into()).await;
+			assert_matches!(result, Err(CreateProjectError::InvalidPath { .. }));
+		}
+	}
+
+	#[test]
+	fn statistics_interval_clamped() {
+		assert_eq!(statistics_interval(None), DEFAULT_STATISTICS_INTERVAL);
+		assert_eq!(
+			statistics_interval(Some(Duration::from_millis(1))),
+			MINIMUM_STATISTICS_INTERVAL
+		);
+		assert_eq!(statistics_interval(Some(Duration::from_secs(5))), Duration::from_secs(5));
+	}
+
+	#[cfg(target_os = "linux")]
+	struct FakeProcess {
+		pid: i32,
+		ppid: i32,
+		utime: u64,
+		rss_pages: u64,
+		threads: u64,
+		voluntary_context_switches: u64,
+		read_bytes: u64,
+	}
+
+	#[cfg(target_os = "linux")]
+	impl FakeProcess {
+		fn write(&self, proc_root: &Path, children: Option<&[i32]>) {
+			let Self {
+				pid,
+				ppid,
+				utime,
+				rss_pages,
+				threads,
+				voluntary_context_switches,
+				read_bytes,
+			} = *self;
+			let dir = proc_root.join(pid.to_string());
+			std::fs::create_dir_all(dir.join("task").join(pid.to_string())).unwrap();
+
+			let stat = format!(
+				"{pid} (fake) S {ppid} {pid} {pid} 0 -1 0 0 0 0 0 {utime} {utime} 0 0 20 0 {threads} 0 0 0 {rss_pages} 0{}",
+				" 0".repeat(27)
+			);
+			std::fs::write(dir.join("stat"), stat).unwrap();
+
+			let status = [
+				"Name:\tfake".to_owned(),
+				"State:\tS (sleeping)".to_owned(),
+				format!("Tgid:\t{pid}\nPid:\t{pid}\nPPid:\t{ppid}\nTracerPid:\t0"),
+				"Uid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\nFDSize:\t64\nGroups:\t".to_owned(),
+				format!("Threads:\t{threads}\nSigQ:\t0/0"),
+				"SigPnd:\t0\nShdPnd:\t0\nSigBlk:\t0\nSigIgn:\t0\nSigCgt:\t0".to_owned(),
+				"CapInh:\t0\nCapPrm:\t0\nCapEff:\t0".to_owned(),
+				format!("voluntary_ctxt_switches:\t{voluntary_context_switches}"),
+				"nonvoluntary_ctxt_switches:\t1\n".to_owned(),
+			];
+			std::fs::write(dir.join("status"), status.join("\n")).unwrap();
+
+			let io = format!(
+				"rchar: 0\nwchar: 0\nsyscr: 0\nsyscw: 0\nread_bytes: {read_bytes}\nwrite_bytes: 0\ncancelled_write_bytes: 0\n"
+			);
+			std::fs::write(dir.join("io"), io).unwrap();
+
+			if let Some(children) = children {
+				let children = children.iter().map(|pid| format!("{pid} ")).collect::<String>();
+				std::fs::write(dir.join("task").join(pid.to_string()).join("children"), children)
+					.unwrap();
+			}
+		}
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn statistics_cover_process_tree() {
+		let fake = |pid, ppid, scale| FakeProcess {
+			pid,
+			ppid,
+			utime: 10 * scale,
+			rss_pages: 100 * scale,
+			threads: scale,
+			voluntary_context_switches: 5 * scale,
+			read_bytes: 1000 * scale,
+		};
+		let cargo = fake(100, 1, 1);
+		let program = fake(101, 100, 3);
+		let unrelated = fake(200, 1, 1000);
+
+		// With `CONFIG_PROC_CHILDREN` and without
+		for has_children_files in [true, false] {
+			let proc_root = tempdir::TempDir::new("proc").unwrap();
+			let children = |c: &'static [i32]| has_children_files.then_some(c);
+			cargo.write(proc_root.path(), children(&[101]));
+			program.write(proc_root.path(), children(&[]));
+			unrelated.write(proc_root.path(), children(&[]));
+
+			let process = stats::Process::with_proc_root(100, proc_root.path().into()).unwrap();
+			let stats = process.stats().unwrap();
+
+			let ticks = procfs::ticks_per_second() as f64;
+			assert_eq!(stats.user_time_secs, 40.0 / ticks, "{has_children_files}");
+			assert_eq!(stats.total_time_secs, 80.0 / ticks, "{has_children_files}");
+			let rss = 400 * procfs::page_size();
+			assert_eq!(stats.resident_set_size_bytes, rss, "{has_children_files}");
+			assert_eq!(stats.peak_resident_set_size_bytes, rss, "{has_children_files}");
+			assert_eq!(stats.threads, Some(4), "{has_children_files}");
+			assert_eq!(stats.voluntary_context_switches, Some(20), "{has_children_files}");
+			assert_eq!(stats.involuntary_context_switches, Some(2), "{has_children_files}");
+			assert_eq!(stats.read_bytes, Some(4000), "{has_children_files}");
+		}
+	}
+}
 
 async fn copy_child_output(
 	output: impl AsyncRead + Unpin,
 	coordinator_tx: MultiplexingSender,
-	mut xform: impl FnMut(String) -> WorkerMessage,
+	limit: OutputLimit,
+	mut xform: impl FnMut(OutputChunk) -> WorkerMessage,
 ) -> Result<(), CopyChildOutputError> {
 	use copy_child_output_error::*;
 
 	let mut buf = Utf8BufReader::new(output);
 	let mut n_total_bytes: usize = 0;
+	let mut head_and_tail = HeadAndTail::new(limit.bytes);
 
 	while let Some(buffer) = buf.next().await.context(UnableToReadSnafu)? {
 		let n_bytes = buffer.len();
 
-		coordinator_tx.send_ok(xform(buffer)).await.context(UnableToSendSnafu)?;
+		let buffer = match limit.mode {
+			OutputLimitMode::Abort => buffer,
+			OutputLimitMode::Truncate => head_and_tail.push(buffer),
+		};
+
+		if !buffer.is_empty() {
+			coordinator_tx.send_ok(xform(buffer)).await.context(UnableToSendSnafu)?;
+		}
 
 		n_total_bytes = n_total_bytes.saturating_add(n_bytes);
-		ensure!(n_total_bytes <= OUTPUT_BYTE_LIMIT, TooManyBytesSnafu { n_total_bytes });
+		if limit.mode == OutputLimitMode::Abort {
+			ensure!(n_total_bytes <= limit.bytes, TooManyBytesSnafu { n_total_bytes, limit });
+		}
+	}
+
+	for buffer in head_and_tail.finish().await.context(UnableToReadSnafu)? {
+		coordinator_tx.send_ok(xform(buffer)).await.context(UnableToSendSnafu)?;
 	}
 
 	Ok(())
 }
 
-const BYTE_LIMIT_URL: &str = "https://github.com/rust-lang/rust-playground/discussions/1027";
+/// Passes output through until half of the limit is used, then only
+/// remembers the most recent output up to the other half.
+struct HeadAndTail {
+	n_head_remaining: usize,
+	n_tail_bytes: usize,
+	tail: VecDeque<u8>,
+	n_omitted: usize,
+}
+
+impl HeadAndTail {
+	fn new(n_bytes: usize) -> Self {
+		let n_head_bytes = n_bytes / 2;
+
+		Self {
+			n_head_remaining: n_head_bytes,
+			n_tail_bytes: n_bytes - n_head_bytes,
+			tail: VecDeque::new(),
+			n_omitted: 0,
+		}
+	}
+
+	// Returns the part of the chunk that should be sent right away.
+	fn push(&mut self, mut head: OutputChunk) -> OutputChunk {
+		let tail = head.split_off(self.n_head_remaining);
+		self.n_head_remaining -= head.len();
+
+		if !tail.is_empty() {
+			// Later output may not be placed in front of this.
+			self.n_head_remaining = 0;
+
+			self.tail.extend(tail.into_bytes());
+			let n_excess = self.tail.len().saturating_sub(self.n_tail_bytes);
+			self.tail.drain(..n_excess);
+			self.n_omitted += n_excess;
+		}
+
+		head
+	}
+
+	async fn finish(mut self) -> Result<Vec<OutputChunk>, Utf8BufReaderError> {
+		let mut chunks = Vec::new();
+
+		if self.n_omitted != 0 {
+			// Don't start in the middle of a character.
+			let is_continuation = |b: &&u8| **b & 0b1100_0000 == 0b1000_0000;
+			let n_continuation = self.tail.iter().take(3).take_while(is_continuation).count();
+			self.tail.drain(..n_continuation);
+			self.n_omitted += n_continuation;
+
+			let note = format!("\n[... {} bytes of output omitted ...]\n", self.n_omitted);
+			chunks.push(OutputChunk::Text(note));
+		}
+
+		let mut tail = Utf8BufReader::new(&*self.tail.make_contiguous());
+		while let Some(chunk) = tail.next().await? {
+			chunks.push(chunk);
+		}
+
+		Ok(chunks)
+	}
+}
 
 #[derive(Debug, Snafu)]
 #[snafu(module)]
@@ -1182,9 +2473,9 @@ pub enum CopyChildOutputError {
 	UnableToSend { source: MultiplexingSenderError },
 
 	#[snafu(display(
-        "Generated {n_total_bytes} bytes of output, exiting (640K ought to be enough for anybody). If this was not an accident, tell us more at {BYTE_LIMIT_URL}"
+        "Generated {n_total_bytes} bytes of output, exiting. The limit of {} bytes can be raised with `ASA_OUTPUT_LIMIT_BYTES`, or set `ASA_OUTPUT_LIMIT_MODE=truncate` to only keep the start and end of the output", limit.bytes
     ))]
-	TooManyBytes { n_total_bytes: usize },
+	TooManyBytes { n_total_bytes: usize, limit: OutputLimit },
 }
 
 // stdin/out <--> messages.
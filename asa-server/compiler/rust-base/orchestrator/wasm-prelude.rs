@@ -0,0 +1,66 @@
+//! Added to builds that ask for it, so that panics and printed output
+//! reach the page instead of disappearing. The page registers where
+//! they go by calling `asaSetOutput` from the JavaScript glue.
+//!
+//! The module is declared with `#[macro_use]` before the rest of the
+//! code, so `print!` and friends are replaced in every module.
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+	static OUTPUT: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+#[wasm_bindgen]
+extern "C" {
+	#[wasm_bindgen(js_namespace = console)]
+	fn log(text: &str);
+}
+
+/// `callback` is called with `("stdout" | "stderr", text)` for
+/// everything printed and for panic messages.
+#[wasm_bindgen(js_name = asaSetOutput)]
+pub fn asa_set_output(callback: js_sys::Function) {
+	OUTPUT.with(|output| *output.borrow_mut() = Some(callback));
+
+	std::panic::set_hook(Box::new(|info| {
+		write("stderr", &format!("{info}\n"));
+		console_error_panic_hook::hook(info);
+	}));
+}
+
+#[doc(hidden)]
+pub fn write(stream: &str, text: &str) {
+	OUTPUT.with(|output| match &*output.borrow() {
+		Some(callback) => {
+			let _ = callback.call2(&JsValue::NULL, &stream.into(), &text.into());
+		}
+		None => log(text),
+	});
+}
+
+#[macro_export]
+macro_rules! print {
+	($($arg:tt)*) => { $crate::asa_prelude::write("stdout", &format!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! println {
+	() => { $crate::print!("\n") };
+	($($arg:tt)*) => { $crate::print!("{}\n", format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! eprint {
+	($($arg:tt)*) => { $crate::asa_prelude::write("stderr", &format!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! eprintln {
+	() => { $crate::eprint!("\n") };
+	($($arg:tt)*) => { $crate::eprint!("{}\n", format_args!($($arg)*)) };
+}
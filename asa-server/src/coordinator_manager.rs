@@ -1,7 +1,8 @@
+use std::env;
 use std::future::Future;
 use std::sync::Arc;
 
-use orchestrator::coordinator::{self, Coordinator, DockerBackend};
+use orchestrator::coordinator::{self, CargoCache, Coordinator, DockerBackend};
 use rocket::tokio::sync::Semaphore;
 use rocket::tokio::task::JoinSet;
 use snafu::{OptionExt, ResultExt, Snafu};
@@ -49,13 +50,34 @@ impl CoordinatorManager {
 
 	pub async fn new() -> Self {
 		Self {
-			coordinator: Arc::new(Coordinator::new_docker().await),
+			coordinator: Arc::new(Coordinator::new(Self::docker_backend()).await),
 			tasks: Default::default(),
 			semaphore: Arc::new(Semaphore::new(Self::N_PARALLEL)),
 			abort_handle: None,
 		}
 	}
 
+	/// The Cargo cache volumes are opt-in:
+	///
+	/// - `ASA_CARGO_CACHE` enables them, using its value as the volume name
+	///   prefix (or the default prefix when empty).
+	/// - `ASA_CARGO_CACHE_MAX_BYTES` removes a channel's volumes on startup
+	///   once they grow larger than the given size.
+	fn docker_backend() -> DockerBackend {
+		let Ok(volume_prefix) = env::var("ASA_CARGO_CACHE") else {
+			return DockerBackend::default();
+		};
+
+		let mut cargo_cache = CargoCache::default();
+		if !volume_prefix.is_empty() {
+			cargo_cache.volume_prefix = volume_prefix;
+		}
+		cargo_cache.max_size_bytes =
+			env::var("ASA_CARGO_CACHE_MAX_BYTES").ok().and_then(|v| v.parse().ok());
+
+		DockerBackend::with_cargo_cache(cargo_cache)
+	}
+
 	pub fn is_empty(&self) -> bool { self.tasks.is_empty() }
 
 	pub async fn join_next(&mut self) -> Option<Result<Result<(), Error>, tokio::task::JoinError>> {
@@ -1,26 +1,29 @@
 #[macro_use]
 extern crate rocket;
 
+use std::future::Future;
 use std::io::prelude::*;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use std::{fs, io};
 
 use async_channel::{unbounded, Receiver, Sender};
 use async_mutex::Mutex;
-use coordinator_manager::CoordinatorManager;
+use coordinator_manager::{CoordinatorManager, SharedCoordinator};
 use glob::glob;
 use orchestrator::coordinator;
-use orchestrator::coordinator::{CompileResponse, CompiledCode, WithOutput};
+use orchestrator::coordinator::{CompileResponse, CompiledCode, FileContent, WithOutput};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::fs::{relative, FileServer, Options};
-use rocket::futures::TryFutureExt;
+use rocket::futures::{StreamExt, TryFutureExt};
 use rocket::http::Header;
 use rocket::serde::json::Json;
 use rocket::{Request, Response, State};
 use serde::{Deserialize, Serialize};
-use tar::Archive;
+use tokio::sync::mpsc;
 use tokio::task::JoinError;
+use tokio_util::sync::CancellationToken;
 
 use crate::error::*;
 
@@ -53,6 +56,182 @@ struct CompileCodeRequest {
 	source_code: String,
 	package_name: String,
 	language: ProgrammingLanguage,
+	/// Run the `wasm-bindgen-test` tests instead of building the package.
+	#[serde(default)]
+	tests: bool,
+	/// Compile to this text form instead of building the package.
+	#[serde(default)]
+	target: Option<TextTarget>,
+	/// Only return this item of the text form, such as `greet` or `impl
+	/// Foo`.
+	#[serde(default)]
+	item: Option<String>,
+	#[serde(default)]
+	release_profile: ReleaseProfile,
+	/// Report which line of the code each part of the module came from.
+	#[serde(default)]
+	debug_info: bool,
+	/// Send panics and printed output to the page's `asaSetOutput`
+	/// callback.
+	#[serde(default)]
+	console_prelude: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ExecuteCodeRequest {
+	source_code: String,
+	package_name: String,
+	#[serde(default)]
+	mode: Mode,
+	/// How often the program's resource usage is sampled; every second
+	/// when missing.
+	#[serde(default)]
+	statistics_interval_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+struct ReleaseProfile {
+	opt_level: Option<OptLevel>,
+	panic_abort: bool,
+	codegen_units: Option<u32>,
+	wasm_opt: Vec<String>,
+}
+impl From<ReleaseProfile> for coordinator::ReleaseProfile {
+	fn from(value: ReleaseProfile) -> Self {
+		let ReleaseProfile { opt_level, panic_abort, codegen_units, wasm_opt } = value;
+		let opt_level = opt_level.map(Into::into);
+		Self { opt_level, panic_abort, codegen_units, wasm_opt }
+	}
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
+enum OptLevel {
+	#[serde(rename = "0")]
+	Zero,
+	#[serde(rename = "1")]
+	One,
+	#[serde(rename = "2")]
+	Two,
+	#[serde(rename = "3")]
+	Three,
+	#[serde(rename = "s")]
+	Size,
+	#[serde(rename = "z")]
+	MinSize,
+}
+impl From<OptLevel> for coordinator::OptLevel {
+	fn from(value: OptLevel) -> Self {
+		match value {
+			OptLevel::Zero => coordinator::OptLevel::Zero,
+			OptLevel::One => coordinator::OptLevel::One,
+			OptLevel::Two => coordinator::OptLevel::Two,
+			OptLevel::Three => coordinator::OptLevel::Three,
+			OptLevel::Size => coordinator::OptLevel::Size,
+			OptLevel::MinSize => coordinator::OptLevel::MinSize,
+		}
+	}
+}
+
+/// Two compilations whose text output is compared line by line.
+#[derive(Clone, Debug, Deserialize)]
+struct DiffRequest {
+	package_name: String,
+	language: ProgrammingLanguage,
+	before: DiffSide,
+	after: DiffSide,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct DiffSide {
+	source_code: String,
+	#[serde(default)]
+	mode: Mode,
+	target: TextTarget,
+	/// Only compare this function, such as `add` or `Foo::get`.
+	#[serde(default)]
+	item: Option<String>,
+	#[serde(default)]
+	asm_pipeline: AsmPipeline,
+	/// The instruction set of the assembly; other targets ignore it.
+	#[serde(default)]
+	assembly_architecture: AssemblyArchitecture,
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AssemblyArchitecture {
+	#[default]
+	Host,
+	Aarch64,
+	Wasm32,
+}
+impl From<AssemblyArchitecture> for coordinator::AssemblyArchitecture {
+	fn from(value: AssemblyArchitecture) -> Self {
+		match value {
+			AssemblyArchitecture::Host => coordinator::AssemblyArchitecture::Host,
+			AssemblyArchitecture::Aarch64 => coordinator::AssemblyArchitecture::Aarch64,
+			AssemblyArchitecture::Wasm32 => coordinator::AssemblyArchitecture::Wasm32,
+		}
+	}
+}
+
+/// Assembly is always filtered and demangled without hashes; these
+/// steps are optional.
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+struct AsmPipeline {
+	strip_comments: bool,
+	collapse_whitespace: bool,
+}
+impl From<AsmPipeline> for coordinator::AsmPipeline {
+	fn from(value: AsmPipeline) -> Self {
+		let AsmPipeline { strip_comments, collapse_whitespace } = value;
+		Self { strip_comments, collapse_whitespace, ..Default::default() }
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+	Debug,
+	#[default]
+	Release,
+}
+impl From<Mode> for coordinator::Mode {
+	fn from(value: Mode) -> Self {
+		match value {
+			Mode::Debug => coordinator::Mode::Debug,
+			Mode::Release => coordinator::Mode::Release,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TextTarget {
+	Assembly,
+	LlvmIr,
+	Mir,
+	Hir,
+	WasmText,
+}
+impl From<TextTarget> for coordinator::CompileTarget {
+	fn from(value: TextTarget) -> Self {
+		use coordinator::{AssemblyFlavor, DemangleAssembly, ProcessAssembly};
+
+		match value {
+			TextTarget::Assembly => coordinator::CompileTarget::Assembly(
+				AssemblyFlavor::Intel,
+				DemangleAssembly::Demangle,
+				ProcessAssembly::Filter,
+			),
+			TextTarget::LlvmIr => coordinator::CompileTarget::LlvmIr,
+			TextTarget::Mir => coordinator::CompileTarget::Mir,
+			TextTarget::Hir => coordinator::CompileTarget::Hir,
+			TextTarget::WasmText => coordinator::CompileTarget::WasmText(ProcessAssembly::Filter),
+		}
+	}
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -67,6 +246,297 @@ struct TextResponseSuccess {
 	result: String,
 	stdout: String,
 	stderr: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	size_report: Option<SizeReport>,
+	/// Ordered by offset; each line covers the module up to the next one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	line_map: Option<Vec<SourceLine>>,
+	/// Only present for WebAssembly text; describes the whole module.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	wasm_summary: Option<WasmSummary>,
+	/// Only present for assembly with `debug_info`; the lines of
+	/// `result`, in order.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	asm_lines: Option<Vec<AsmLine>>,
+	/// Only present for assembly; one entry for each function, or only
+	/// for `item` when it was found.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	asm_stats: Option<Vec<AsmFunctionStats>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AsmLine {
+	text: String,
+	/// The line of the code this instruction came from; only
+	/// instructions have one.
+	source_line: Option<u64>,
+}
+
+impl From<coordinator::AsmLine> for AsmLine {
+	fn from(value: coordinator::AsmLine) -> Self {
+		Self { text: value.text, source_line: value.source_line }
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AsmFunctionStats {
+	name: String,
+	instructions: usize,
+	calls: usize,
+	/// Jumps to labels; returns and calls aren't counted.
+	branches: usize,
+	vector_instructions: usize,
+	/// Calls to the panics for an out of bounds index or range.
+	bounds_checks: usize,
+	stack_frame_size: u64,
+}
+
+impl From<coordinator::AsmFunctionStats> for AsmFunctionStats {
+	fn from(value: coordinator::AsmFunctionStats) -> Self {
+		Self {
+			name: value.name,
+			instructions: value.instructions,
+			calls: value.calls,
+			branches: value.branches,
+			vector_instructions: value.vector_instructions,
+			bounds_checks: value.bounds_checks,
+			stack_frame_size: value.stack_frame_size,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WasmSummary {
+	imports: Vec<WasmImport>,
+	exports: Vec<WasmExport>,
+	functions: Vec<WasmFunction>,
+	data_segments: Vec<WasmDataSegment>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WasmImport {
+	module: String,
+	name: String,
+	kind: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WasmExport {
+	name: String,
+	kind: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WasmFunction {
+	name: String,
+	instructions: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WasmDataSegment {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	name: Option<String>,
+	size_bytes: usize,
+}
+
+impl From<coordinator::WasmSummary> for WasmSummary {
+	fn from(value: coordinator::WasmSummary) -> Self {
+		let imports = value
+			.imports
+			.into_iter()
+			.map(|i| WasmImport { module: i.module, name: i.name, kind: i.kind })
+			.collect();
+		let exports =
+			value.exports.into_iter().map(|e| WasmExport { name: e.name, kind: e.kind }).collect();
+		let functions = value
+			.functions
+			.into_iter()
+			.map(|f| WasmFunction { name: f.name, instructions: f.instructions })
+			.collect();
+		let data_segments = value
+			.data_segments
+			.into_iter()
+			.map(|d| WasmDataSegment { name: d.name, size_bytes: d.size_bytes })
+			.collect();
+		Self { imports, exports, functions, data_segments }
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct SourceLine {
+	/// Matches the offsets shown in browser stack traces.
+	offset: u64,
+	line: u64,
+	column: u64,
+}
+
+impl From<coordinator::LineMapping> for SourceLine {
+	fn from(value: coordinator::LineMapping) -> Self {
+		Self { offset: value.address, line: value.line, column: value.column }
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct SizeReport {
+	total_bytes: u64,
+	/// Only the largest functions, largest first.
+	functions: Vec<FunctionSize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct FunctionSize {
+	name: String,
+	size_bytes: usize,
+}
+
+impl From<coordinator::WasmSizeReport> for SizeReport {
+	fn from(value: coordinator::WasmSizeReport) -> Self {
+		const MAX_FUNCTIONS: usize = 20;
+
+		let functions = value
+			.functions
+			.into_iter()
+			.take(MAX_FUNCTIONS)
+			.map(|f| FunctionSize { name: f.name, size_bytes: f.size_bytes })
+			.collect();
+		Self { total_bytes: value.total_bytes, functions }
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct TestRunResult {
+	success: bool,
+	exit_detail: String,
+	tests: Vec<coordinator::TestResult>,
+	stdout: String,
+	stderr: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ExecuteSuccess {
+	success: bool,
+	exit_detail: String,
+	stdout: String,
+	stderr: String,
+	/// The last sample taken before the program exited.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	statistics: Option<ExecuteStatistics>,
+	/// Every sample taken while the program ran, oldest first.
+	samples: Vec<ExecuteStatistics>,
+}
+
+/// Statistics that aren't available on the worker's platform are left
+/// out.
+#[derive(Clone, Debug, Serialize)]
+struct ExecuteStatistics {
+	resident_set_size_bytes: u64,
+	peak_resident_set_size_bytes: u64,
+	total_time_secs: f64,
+	user_time_secs: f64,
+	system_time_secs: f64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	voluntary_context_switches: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	involuntary_context_switches: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	read_bytes: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	written_bytes: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	threads: Option<u64>,
+}
+
+impl From<coordinator::ExecuteStatus> for ExecuteStatistics {
+	fn from(value: coordinator::ExecuteStatus) -> Self {
+		Self {
+			resident_set_size_bytes: value.resident_set_size_bytes,
+			peak_resident_set_size_bytes: value.peak_resident_set_size_bytes,
+			total_time_secs: value.total_time_secs,
+			user_time_secs: value.user_time_secs,
+			system_time_secs: value.system_time_secs,
+			voluntary_context_switches: value.voluntary_context_switches,
+			involuntary_context_switches: value.involuntary_context_switches,
+			read_bytes: value.read_bytes,
+			written_bytes: value.written_bytes,
+			threads: value.threads,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DiffSuccess {
+	before: DiffOutput,
+	after: DiffOutput,
+	/// Both outputs interleaved, in order. Label numbers and hashes are
+	/// ignored when comparing lines.
+	diff: Vec<DiffLine>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DiffOutput {
+	result: String,
+	stdout: String,
+	stderr: String,
+	/// Only present for assembly.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	functions: Option<Vec<FunctionStats>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct FunctionStats {
+	name: String,
+	instructions: usize,
+	calls: usize,
+	branches: usize,
+	vector_instructions: usize,
+	bounds_checks: usize,
+	stack_frame_size: u64,
+}
+
+impl From<coordinator::AsmFunctionStats> for FunctionStats {
+	fn from(value: coordinator::AsmFunctionStats) -> Self {
+		Self {
+			name: value.name,
+			instructions: value.instructions,
+			calls: value.calls,
+			branches: value.branches,
+			vector_instructions: value.vector_instructions,
+			bounds_checks: value.bounds_checks,
+			stack_frame_size: value.stack_frame_size,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DiffLine {
+	kind: DiffKind,
+	before_line: Option<usize>,
+	after_line: Option<usize>,
+	text: String,
+}
+
+#[derive(Copy, Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DiffKind {
+	Same,
+	Removed,
+	Added,
+}
+
+impl From<asm_cleanup::DiffLine> for DiffLine {
+	fn from(value: asm_cleanup::DiffLine) -> Self {
+		let kind = match value.kind {
+			asm_cleanup::DiffKind::Same => DiffKind::Same,
+			asm_cleanup::DiffKind::Removed => DiffKind::Removed,
+			asm_cleanup::DiffKind::Added => DiffKind::Added,
+		};
+		Self {
+			kind,
+			before_line: value.before_line,
+			after_line: value.after_line,
+			text: value.text,
+		}
+	}
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -93,6 +563,15 @@ enum CompileCodeResponse {
 	#[response(status = 200)]
 	TextSuccess(JsonResponse<TextResponseSuccess>),
 
+	#[response(status = 200)]
+	TestResults(JsonResponse<TestRunResult>),
+
+	#[response(status = 200)]
+	Diff(JsonResponse<DiffSuccess>),
+
+	#[response(status = 200)]
+	Execution(JsonResponse<ExecuteSuccess>),
+
 	#[response(status = 400)]
 	CompileError(JsonResponse<CompileFailed>),
 
@@ -103,20 +582,53 @@ enum CompileCodeResponse {
 	CompileCancelled(String),
 }
 
+/// `ASA_OUTPUT_LIMIT_BYTES` overrides how much output a compile may
+/// produce, and `ASA_OUTPUT_LIMIT_MODE=truncate` keeps the start and end
+/// of larger output instead of aborting the compile.
+fn output_limit() -> Option<coordinator::OutputLimit> {
+	let bytes = std::env::var("ASA_OUTPUT_LIMIT_BYTES").ok().and_then(|b| b.parse().ok());
+	let mode = std::env::var("ASA_OUTPUT_LIMIT_MODE").ok().map(|m| match m.as_str() {
+		"truncate" => coordinator::OutputLimitMode::Truncate,
+		_ => coordinator::OutputLimitMode::Abort,
+	});
+
+	if bytes.is_none() && mode.is_none() {
+		return None;
+	}
+
+	let default = coordinator::OutputLimit::default();
+	Some(coordinator::OutputLimit {
+		bytes: bytes.unwrap_or(default.bytes),
+		mode: mode.unwrap_or(default.mode),
+	})
+}
+
 async fn do_compile(
-	shared_coordinator: coordinator_manager::SharedCoordinator,
+	shared_coordinator: SharedCoordinator,
 	req: CompileCodeRequest,
 	sender: Sender<CompileCodeResponse>,
 	request_index: usize,
 ) -> Result<(), Error> {
 	let package_name = req.package_name.clone();
+	let target = match (req.target, req.tests) {
+		(Some(target), _) => target.into(),
+		(None, true) => coordinator::CompileTarget::WasmTest,
+		(None, false) => coordinator::CompileTarget::Wasm,
+	};
 	let req = coordinator::CompileRequest {
-		target: coordinator::CompileTarget::Wasm,
+		target,
 		language: req.language.into(),
 		crate_type: coordinator::CrateType::Library(coordinator::LibraryType::Cdylib),
 		mode: coordinator::Mode::Release,
 		code: req.source_code.to_string(),
 		package_name: req.package_name,
+		output_limit: output_limit(),
+		release_profile: req.release_profile.into(),
+		debug_info: req.debug_info,
+		console_prelude: req.console_prelude,
+		item: req.item,
+		asm_pipeline: Default::default(),
+		assembly_architecture: Default::default(),
 	};
 
 	let with_output_res = shared_coordinator.compile(req).await;
@@ -124,6 +636,18 @@ async fn do_compile(
 	let response: CompileCodeResponse = match with_output_res {
 		Ok(res) => {
 			match res {
+				// Tests that ran are reported even if some of them failed
+				WithOutput {
+					response: CompileResponse { success, exit_detail, tests, .. },
+					stdout,
+					stderr,
+				} if target == coordinator::CompileTarget::WasmTest
+					&& (success || !tests.is_empty()) =>
+				{
+					CompileCodeResponse::TestResults(
+						TestRunResult { success, exit_detail, tests, stdout, stderr }.into(),
+					)
+				}
 				WithOutput {
 					response: CompileResponse { success: false, exit_detail, .. },
 					stdout,
@@ -131,13 +655,10 @@ async fn do_compile(
 				} => CompileCodeResponse::CompileError(
 					CompileFailed { exit_detail, stdout, stderr }.into(),
 				),
-				WithOutput {
-					response: CompileResponse { success: true, code, .. },
-					stdout,
-					stderr,
-				} => extract_code_response(code, package_name, request_index, stdout, stderr),
-				// other => {CompileCodeResponse::InternalError(format!("Unknown problem with
-				// compile: {other:?}")) }
+				WithOutput { response, stdout, stderr } => {
+					extract_code_response(response, package_name, request_index, stdout, stderr)
+				} /* other => {CompileCodeResponse::InternalError(format!("Unknown problem with
+				   * compile: {other:?}")) } */
 			}
 		}
 		Err(e) => {
@@ -145,17 +666,160 @@ async fn do_compile(
 			CompileCodeResponse::InternalError(format!("Unknown problem with compile: {e:?}"))
 		}
 	};
+	send_response(&sender, response).await
+}
+
+async fn do_execute(
+	shared_coordinator: SharedCoordinator,
+	req: ExecuteCodeRequest,
+	sender: Sender<CompileCodeResponse>,
+) -> Result<(), Error> {
+	let req = coordinator::ExecuteRequest {
+		channel: coordinator::RustChannel::Stable,
+		mode: req.mode.into(),
+		edition: coordinator::RustEdition::Rust2021,
+		crate_type: coordinator::CrateType::Binary,
+		tests: false,
+		backtrace: false,
+		code: req.source_code,
+		package_name: req.package_name,
+		statistics_interval: req.statistics_interval_ms.map(Duration::from_millis),
+		output_limit: output_limit(),
+	};
+
+	let coordinator::ActiveExecution { task, stdin_tx, stdout_rx, stderr_rx, status_rx } =
+		match shared_coordinator.begin_execute(CancellationToken::new(), req).await {
+			Ok(active) => active,
+			Err(e) => {
+				let response = CompileCodeResponse::InternalError(format!(
+					"Unknown problem with execute: {e:?}"
+				));
+				return send_response(&sender, response).await;
+			}
+		};
+	drop(stdin_tx);
+
+	let samples = status_rx.map(Into::into).collect::<Vec<_>>();
+	let (response, stdout, stderr, samples) =
+		rocket::tokio::join!(task, collect_output(stdout_rx), collect_output(stderr_rx), samples);
+
+	let response = match response {
+		Ok(coordinator::ExecuteResponse { success, exit_detail, statistics }) => {
+			let statistics = statistics.map(Into::into);
+			CompileCodeResponse::Execution(
+				ExecuteSuccess { success, exit_detail, stdout, stderr, statistics, samples }.into(),
+			)
+		}
+		Err(e) => {
+			CompileCodeResponse::InternalError(format!("Unknown problem with execute: {e:?}"))
+		}
+	};
+	send_response(&sender, response).await
+}
+
+async fn collect_output(mut output_rx: mpsc::Receiver<coordinator::OutputChunk>) -> String {
+	let mut output = String::new();
+	while let Some(chunk) = output_rx.recv().await {
+		output.push_str(&String::from(chunk));
+	}
+	output
+}
+
+async fn do_diff(
+	shared_coordinator: SharedCoordinator,
+	req: DiffRequest,
+	sender: Sender<CompileCodeResponse>,
+) -> Result<(), Error> {
+	let DiffRequest { package_name, language, before, after } = req;
+
+	let mut outputs = Vec::new();
+	for (side, spec) in [("before", before), ("after", after)] {
+		let req = coordinator::CompileRequest {
+			target: spec.target.into(),
+			language: language.into(),
+			crate_type: coordinator::CrateType::Library(coordinator::LibraryType::Cdylib),
+			mode: spec.mode.into(),
+			code: spec.source_code,
+			package_name: format!("{package_name}_{side}"),
+			output_limit: output_limit(),
+			release_profile: Default::default(),
+			debug_info: false,
+			console_prelude: false,
+			item: spec.item,
+			asm_pipeline: spec.asm_pipeline.into(),
+			assembly_architecture: spec.assembly_architecture.into(),
+		};
+
+		let output = match shared_coordinator.compile(req).await {
+			Ok(WithOutput {
+				response:
+					CompileResponse {
+						success: true,
+						code: CompiledCode::CodeStr(result),
+						asm_stats,
+						..
+					},
+				stdout,
+				stderr,
+			}) => {
+				let functions = asm_stats.map(|stats| stats.into_iter().map(Into::into).collect());
+				DiffOutput { result, stdout, stderr, functions }
+			}
+			Ok(WithOutput { response: CompileResponse { exit_detail, .. }, stdout, stderr }) => {
+				let exit_detail = format!("The {side} code failed: {exit_detail}");
+				let response = CompileCodeResponse::CompileError(
+					CompileFailed { exit_detail, stdout, stderr }.into(),
+				);
+				return send_response(&sender, response).await;
+			}
+			Err(e) => {
+				let response = CompileCodeResponse::InternalError(format!(
+					"Unknown problem with the {side} compile: {e:?}"
+				));
+				return send_response(&sender, response).await;
+			}
+		};
+		outputs.push(output);
+	}
+
+	let after = outputs.pop().expect("both sides were compiled");
+	let before = outputs.pop().expect("both sides were compiled");
+	let diff = asm_cleanup::diff_output(&before.result, &after.result)
+		.into_iter()
+		.map(Into::into)
+		.collect();
+
+	send_response(&sender, CompileCodeResponse::Diff(DiffSuccess { before, after, diff }.into()))
+		.await
+}
+
+async fn send_response(
+	sender: &Sender<CompileCodeResponse>,
+	response: CompileCodeResponse,
+) -> Result<(), Error> {
 	sender
 		.send(response)
 		.await
-		.map_err(|err| ResultChannelFailedSnafu { text: format!("{err}") }.build())?;
-
-	Ok(())
+		.map_err(|err| ResultChannelFailedSnafu { text: format!("{err}") }.build())
 }
 
-fn try_unarchiving(result: &[u8], output_location: PathBuf) -> io::Result<()> {
-	let mut archive = Archive::new(result);
-	archive.unpack(output_location)
+fn write_package(files: &[FileContent], output_location: &Path) -> io::Result<()> {
+	for file in files {
+		// Never write outside of the package directory
+		let relative = Path::new(&file.path);
+		if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+			let message = format!("Invalid package file path {:?}", file.path);
+			return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+		}
+
+		let path = output_location.join(relative);
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(path, &file.content)?;
+	}
+
+	Ok(())
 }
 
 fn get_js_glue_file_name(package_location: PathBuf) -> Result<String, String> {
@@ -194,35 +858,64 @@ fn get_js_glue_file(package_location: PathBuf) -> Result<String, String> {
 }
 
 fn extract_code_response(
-	code: CompiledCode,
+	response: CompileResponse,
 	package_name: String,
 	request_index: usize,
 	stdout: String,
 	stderr: String,
 ) -> CompileCodeResponse {
-	if let CompiledCode::CodeBin(result) = code {
-		let output_location = format!("./pkg/{package_name}");
-		let output_location = Path::new(output_location.as_str());
+	let CompileResponse { code, size_report, line_map, wasm_summary, asm_lines, asm_stats, .. } =
+		response;
+
+	match code {
+		CompiledCode::Package(files) => {
+			let output_location = format!("./pkg/{package_name}");
+			let output_location = Path::new(output_location.as_str());
+
+			let _ = fs::remove_dir_all(output_location);
 
-		let _ = fs::remove_dir_all(output_location);
+			if let Err(e) = write_package(&files, output_location) {
+				return CompileCodeResponse::InternalError(format!(
+					"Failed to write the package to {output_location:?}: {e}"
+				));
+			}
+
+			let size_report = size_report.map(Into::into);
+			let line_map = line_map.map(|m| m.lines.into_iter().map(Into::into).collect());
 
-		if let Ok(_) = try_unarchiving(&result, output_location.into()) {
 			match get_js_glue_file_name(output_location.into()) {
 				Ok(result) => CompileCodeResponse::TextSuccess(
-					TextResponseSuccess { result, stdout, stderr }.into(),
+					TextResponseSuccess {
+						result,
+						stdout,
+						stderr,
+						size_report,
+						line_map,
+						wasm_summary: None,
+						asm_lines: None,
+						asm_stats: None,
+					}
+					.into(),
 				),
 				Err(response) => CompileCodeResponse::InternalError(response),
 			}
-		} else {
-			// TODO: This is a bad fallback...
+		}
+		CompiledCode::CodeBin(result) => {
 			CompileCodeResponse::Success(CompileSuccess { result, stdout, stderr }.into())
 		}
-	} else if let CompiledCode::CodeStr(result) = code {
-		CompileCodeResponse::TextSuccess(TextResponseSuccess { result, stdout, stderr }.into())
-	} else {
-		CompileCodeResponse::InternalError(format!(
-			"Received unknown data type after compile: {code:?}"
-		))
+		CompiledCode::CodeStr(result) => CompileCodeResponse::TextSuccess(
+			TextResponseSuccess {
+				result,
+				stdout,
+				stderr,
+				size_report: None,
+				line_map: None,
+				wasm_summary: wasm_summary.map(Into::into),
+				asm_lines: asm_lines.map(|lines| lines.into_iter().map(Into::into).collect()),
+				asm_stats: asm_stats.map(|stats| stats.into_iter().map(Into::into).collect()),
+			}
+			.into(),
+		),
 	}
 }
 
@@ -255,18 +948,63 @@ async fn compile_code(
 	let current_request = counter.fetch_add(1, Ordering::Relaxed);
 	println!("Compile request {} received: {:?}", current_request, code_request);
 
+	let request_inner = code_request.0.clone();
+	run_on_coordinator(manager, move |shared_coordinator, sender| {
+		do_compile(shared_coordinator, request_inner, sender, current_request)
+	})
+	.await
+}
+
+#[post("/execute", data = "<execute_request>")]
+async fn execute_code(
+	execute_request: Json<ExecuteCodeRequest>,
+	manager: &State<Mutex<CoordinatorManager>>,
+	counter: &State<AtomicUsize>,
+) -> CompileCodeResponse {
+	let current_request = counter.fetch_add(1, Ordering::Relaxed);
+	println!("Execute request {} received: {:?}", current_request, execute_request);
+
+	let request_inner = execute_request.0.clone();
+	run_on_coordinator(manager, move |shared_coordinator, sender| {
+		do_execute(shared_coordinator, request_inner, sender)
+	})
+	.await
+}
+
+#[post("/diff", data = "<diff_request>")]
+async fn diff_code(
+	diff_request: Json<DiffRequest>,
+	manager: &State<Mutex<CoordinatorManager>>,
+	counter: &State<AtomicUsize>,
+) -> CompileCodeResponse {
+	let current_request = counter.fetch_add(1, Ordering::Relaxed);
+	println!("Diff request {} received: {:?}", current_request, diff_request);
+
+	let request_inner = diff_request.0.clone();
+	run_on_coordinator(manager, move |shared_coordinator, sender| {
+		do_diff(shared_coordinator, request_inner, sender)
+	})
+	.await
+}
+
+async fn run_on_coordinator<F, Fut>(
+	manager: &State<Mutex<CoordinatorManager>>,
+	handler: F,
+) -> CompileCodeResponse
+where
+	F: FnOnce(SharedCoordinator, Sender<CompileCodeResponse>) -> Fut,
+	F: 'static + Send,
+	Fut: Future<Output = Result<(), Error>>,
+	Fut: 'static + Send,
+{
 	// TODO: Effectively sequential now
 	let mut locked_manager = manager.lock().await;
 
 	let (sender, receiver): (Sender<CompileCodeResponse>, Receiver<CompileCodeResponse>) =
 		unbounded();
 
-	let request_inner = code_request.0.clone();
-	let _spawned = locked_manager
-		.spawn(move |shared_coordinator| {
-			do_compile(shared_coordinator, request_inner, sender, current_request)
-		})
-		.await;
+	let _spawned =
+		locked_manager.spawn(move |shared_coordinator| handler(shared_coordinator, sender)).await;
 
 	let response = {
 		if let Some(task) = locked_manager.join_next().await {
@@ -309,6 +1047,6 @@ async fn rocket() -> _ {
 		.manage(Mutex::new(CoordinatorManager::new().await))
 		.manage(AtomicUsize::new(0))
 		.attach(CORS)
-		.mount("/", routes![compile_code])
+		.mount("/", routes![compile_code, execute_code, diff_code])
 		.mount("/pkg", FileServer::new(relative!("../pkg"), Options::None | Options::Missing))
 }
@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Names that code can `use` without depending on a crate
+const BUILTIN_NAMES: &[&str] = &["alloc", "core", "crate", "proc_macro", "self", "std", "super"];
+
+/// Files with snippets in them; anything else in a deck directory is
+/// skipped.
+const SNIPPET_EXTENSIONS: &[&str] = &["rs", "html", "htm", "md"];
+
+/// Lists the crates a deck needs, with its own files in `snippets`. Our
+/// deck manifest format looks like:
+///
+/// ```toml
+/// snippets = ["slides/", "extra.rs"]
+/// crates = ["web-sys"]
+/// ```
+///
+/// Snippet paths are relative to the manifest. `crates` are included even
+/// if no snippet names them. All fields are optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeckManifest {
+	#[serde(default)]
+	pub snippets: Vec<PathBuf>,
+	#[serde(default)]
+	pub crates: BTreeSet<String>,
+}
+
+/// Finds the crates used by the decks at `paths`, each a deck manifest
+/// (`.toml`), a file with snippets in it, or a directory of those files.
+///
+/// The names are the ones used in code, so they may have underscores
+/// where the crate's name has hyphens.
+pub fn deck_crate_names(paths: &[PathBuf]) -> BTreeSet<String> {
+	let mut snippets = Vec::new();
+	let mut names = BTreeSet::new();
+
+	for path in paths {
+		if path.extension().is_some_and(|e| e == "toml") {
+			let content = fs::read_to_string(path)
+				.unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+			let manifest: DeckManifest = toml::from_str(&content)
+				.unwrap_or_else(|e| panic!("Unable to parse {}: {}", path.display(), e));
+
+			let base = path.parent().unwrap_or_else(|| Path::new(""));
+			for snippet in manifest.snippets {
+				collect_snippet_files(&base.join(snippet), &mut snippets);
+			}
+			names.extend(manifest.crates);
+		} else {
+			collect_snippet_files(path, &mut snippets);
+		}
+	}
+
+	let mut scan = Scan::default();
+	for snippet in snippets {
+		let content = fs::read_to_string(&snippet)
+			.unwrap_or_else(|e| panic!("Unable to read {}: {}", snippet.display(), e));
+		scan.add(&content);
+	}
+
+	names.extend(scan.crate_names());
+	names
+}
+
+fn collect_snippet_files(path: &Path, files: &mut Vec<PathBuf>) {
+	if !path.is_dir() {
+		files.push(path.to_owned());
+		return;
+	}
+
+	let entries =
+		fs::read_dir(path).unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+	let mut entries: Vec<_> = entries
+		.map(|entry| entry.unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e)))
+		.map(|entry| entry.path())
+		.collect();
+	entries.sort();
+
+	for entry in entries {
+		let is_snippet = entry
+			.extension()
+			.and_then(|e| e.to_str())
+			.is_some_and(|e| SNIPPET_EXTENSIONS.contains(&e));
+		if entry.is_dir() || is_snippet {
+			collect_snippet_files(&entry, files);
+		}
+	}
+}
+
+/// The names found in all of a deck's snippets, so that a slide can use a
+/// module declared on another slide.
+#[derive(Debug, Default)]
+struct Scan {
+	used: BTreeSet<String>,
+	local: BTreeSet<String>,
+}
+
+impl Scan {
+	fn add(&mut self, content: &str) {
+		lazy_static! {
+			// Examples:    use wasm_bindgen::prelude::*;    use ::serde;
+			// Prose like "we use the canvas" doesn't match.
+			static ref USE_REGEX: Regex =
+				Regex::new(r"\buse\s+(?:::)?([a-z_][a-zA-Z0-9_]*)\s*(?:::|;|\sas\b)").unwrap();
+			// Example:    extern crate rand;
+			static ref EXTERN_CRATE_REGEX: Regex =
+				Regex::new(r"\bextern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)").unwrap();
+			// Examples:    mod shapes {    <div id="demo_code">
+			static ref LOCAL_REGEX: Regex =
+				Regex::new(r#"\bmod\s+([a-zA-Z_][a-zA-Z0-9_]*)|\bid="([a-zA-Z_][a-zA-Z0-9_]*)""#)
+					.unwrap();
+		}
+
+		for regex in [&*USE_REGEX, &*EXTERN_CRATE_REGEX] {
+			let names = regex.captures_iter(content).filter_map(|cap| cap.get(1));
+			self.used.extend(names.map(|name| name.as_str().to_owned()));
+		}
+
+		let local = LOCAL_REGEX.captures_iter(content).filter_map(|cap| cap.get(1).or(cap.get(2)));
+		self.local.extend(local.map(|name| name.as_str().to_owned()));
+	}
+
+	fn crate_names(self) -> impl Iterator<Item = String> {
+		let Scan { used, local } = self;
+
+		used.into_iter()
+			.filter(move |name| !local.contains(name))
+			.filter(|name| !BUILTIN_NAMES.contains(&name.as_str()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn crate_names(snippets: &[&str]) -> Vec<String> {
+		let mut scan = Scan::default();
+		for snippet in snippets {
+			scan.add(snippet);
+		}
+		scan.crate_names().collect()
+	}
+
+	#[test]
+	fn used_crates_found() {
+		let snippet = r#"
+			extern crate rand;
+			use std::collections::HashMap;
+			use ::serde::Serialize;
+			use wasm_bindgen::prelude::*;
+			use self::shapes::Circle;
+			use Direction::*;
+		"#;
+
+		assert_eq!(crate_names(&[snippet]), ["rand", "serde", "wasm_bindgen"]);
+	}
+
+	#[test]
+	fn other_slides_are_not_crates() {
+		let first = r#"<p>We use the canvas.</p><div id="demo_code">pub mod shapes {}</div>"#;
+		let second = "use demo_code;\nuse shapes::Circle;\nuse web_sys::Window;";
+
+		assert_eq!(crate_names(&[first, second]), ["web_sys"]);
+	}
+}
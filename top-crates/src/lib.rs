@@ -2,18 +2,20 @@
 
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::io::Read;
-use std::mem;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::task::Poll;
+use std::{fs, mem};
 
 use cargo::core::compiler::{CompileKind, CompileTarget, TargetInfo};
+use cargo::core::dependency::DepKind;
 use cargo::core::package::PackageSet;
 use cargo::core::registry::PackageRegistry;
 use cargo::core::resolver::features::RequestedFeatures;
-use cargo::core::resolver::{self, ResolveOpts, VersionPreferences};
+use cargo::core::resolver::{self, Resolve, ResolveOpts, VersionPreferences};
 use cargo::core::{Dependency, Package, PackageId, SourceId, Summary, Target};
 use cargo::sources::source::{QueryKind, Source, SourceMap};
-use cargo::sources::RegistrySource;
+use cargo::sources::SourceConfigMap;
 use cargo::util::cache_lock::CacheLockMode;
 use cargo::util::interning::InternedString;
 use cargo::util::Config;
@@ -22,14 +24,20 @@ use itertools::Itertools;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
+pub use self::deck::{deck_crate_names, DeckManifest};
+
+mod deck;
+
 const PLAYGROUND_TARGET_PLATFORM: &str = "x86_64-unknown-linux-gnu";
+const WASM_TARGET_PLATFORM: &str = "wasm32-unknown-unknown";
 
 struct GlobalState<'cfg> {
 	config: &'cfg Config,
 	target_info: TargetInfo,
+	wasm_target_info: TargetInfo,
 	registry: PackageRegistry<'cfg>,
 	crates_io: SourceId,
-	source: RegistrySource<'cfg>,
+	source: Box<dyn Source + 'cfg>,
 	modifications: &'cfg Modifications,
 }
 
@@ -39,6 +47,29 @@ struct TopCrates {
 	crates: Vec<Crate>,
 }
 
+/// The crates to start resolving from, along with the `required` crates
+#[derive(Debug)]
+pub enum CrateList {
+	/// The most downloaded crates, the Rust Cookbook's crates, and the
+	/// `additions`
+	Popular,
+	/// The crates named in a file, one per line, and the `additions`
+	File(PathBuf),
+	/// Only the crates a deck uses, by the names used in its code
+	Deck(BTreeSet<String>),
+}
+
+/// Where crates are looked up and downloaded from
+#[derive(Debug, Default)]
+pub enum Registry {
+	#[default]
+	CratesIo,
+	/// A local registry, such as one made by `cargo local-registry`
+	Local(PathBuf),
+	/// A directory of crates, as made by `cargo vendor`
+	Vendored(PathBuf),
+}
+
 /// The shared description of a crate
 #[derive(Debug, Deserialize)]
 struct Crate {
@@ -52,15 +83,54 @@ pub struct CrateInformation {
 	pub name: String,
 	pub version: Version,
 	pub id: String,
+	/// Whether the crate builds for slides. Crates that don't are left out
+	/// of the playground's `Cargo.toml`.
+	pub wasm: bool,
 }
 
 /// Hand-curated changes to the crate list
 #[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct Modifications {
 	#[serde(default)]
 	pub exclusions: Vec<InternedString>,
 	#[serde(default)]
 	pub additions: BTreeSet<InternedString>,
+	/// Crates that every build needs, which are added to every crate
+	/// list, including a deck's.
+	#[serde(default)]
+	pub required: BTreeSet<InternedString>,
+	/// Crates known not to build for wasm, along with everything that
+	/// depends on them there.
+	#[serde(default)]
+	pub wasm_incompatible: BTreeSet<InternedString>,
+	/// Settings for crates, by name, used whenever they are included.
+	#[serde(default)]
+	pub crates: BTreeMap<String, CrateModification>,
+}
+
+/// Hand-picked settings for one crate, which look like:
+///
+/// ```toml
+/// [crates.web-sys]
+/// version = "0.3.67"
+/// features = ["Document", "WebGl2RenderingContext"]
+/// default-features = true
+/// all-features = false
+/// ```
+///
+/// All fields are optional. Features are added to the ones from the
+/// crate's own playground metadata.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CrateModification {
+	/// A version requirement, like in `Cargo.toml`
+	pub version: Option<String>,
+	#[serde(default)]
+	pub features: BTreeSet<InternedString>,
+	pub default_features: Option<bool>,
+	#[serde(default)]
+	pub all_features: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -74,6 +144,8 @@ pub struct DependencySpec {
 	pub features: BTreeSet<InternedString>,
 	#[serde(skip_serializing_if = "is_true")]
 	pub default_features: bool,
+	#[serde(skip)]
+	pub builds_for_wasm: bool,
 }
 
 #[derive(Debug)]
@@ -82,6 +154,7 @@ struct ResolvedDep {
 	lib_target: Target,
 	features: BTreeSet<InternedString>,
 	uses_default_features: bool,
+	builds_for_wasm: bool,
 }
 
 fn exact_version<S>(version: &Version, serializer: S) -> Result<S::Ok, S::Error>
@@ -102,6 +175,24 @@ fn is_true(b: &bool) -> bool { *b }
 
 impl Modifications {
 	fn excluded(&self, name: &str) -> bool { self.exclusions.iter().any(|n| n == name) }
+
+	fn wasm_incompatible(&self, name: &str) -> bool {
+		self.wasm_incompatible.iter().any(|n| n == name)
+	}
+
+	fn crate_modification(&self, name: &str) -> Option<&CrateModification> { self.crates.get(name) }
+}
+
+impl CrateModification {
+	/// The features to enable; if `all-features` is set then `features` is
+	/// ignored.
+	fn features(&self, summary: &Summary) -> BTreeSet<InternedString> {
+		if self.all_features {
+			summary.features().keys().copied().collect()
+		} else {
+			self.features.clone()
+		}
+	}
 }
 
 fn simple_get(url: &str) -> reqwest::Result<reqwest::blocking::Response> {
@@ -150,9 +241,26 @@ impl TopCrates {
 		})
 	}
 
+	/// List the crates in a file. Blank lines and lines starting with `#`
+	/// are skipped.
+	fn read(path: &Path) -> TopCrates {
+		let content = fs::read_to_string(path)
+			.unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+
+		let crates = content
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(|name| Crate { name: InternedString::new(name) })
+			.collect();
+
+		TopCrates { crates }
+	}
+
 	/// Add crates that have been hand-picked
 	fn add_curated_crates(&mut self, modifications: &Modifications) {
-		self.crates.extend(modifications.additions.iter().copied().map(|name| Crate { name }));
+		let names = modifications.additions.iter().chain(&modifications.required);
+		self.crates.extend(names.copied().map(|name| Crate { name }));
 	}
 }
 
@@ -212,13 +320,9 @@ fn make_global_state<'cfg>(
 	config: &'cfg Config,
 	modifications: &'cfg Modifications,
 ) -> GlobalState<'cfg> {
-	// Information about the playground's target platform.
-	let compile_target =
-		CompileTarget::new(PLAYGROUND_TARGET_PLATFORM).expect("Unable to create a CompileTarget");
-	let compile_kind = CompileKind::Target(compile_target);
-	let rustc = config.load_global_rustc(None).expect("Unable to load the global rustc");
-	let target_info = TargetInfo::new(config, &[compile_kind], &rustc, compile_kind)
-		.expect("Unable to create a TargetInfo");
+	// Information about the playground's target platforms.
+	let target_info = make_target_info(config, PLAYGROUND_TARGET_PLATFORM);
+	let wasm_target_info = make_target_info(config, WASM_TARGET_PLATFORM);
 
 	// Registry of known packages.
 	let mut registry = PackageRegistry::new(config).expect("Unable to create package registry");
@@ -227,12 +331,30 @@ fn make_global_state<'cfg>(
 	// Source for obtaining packages from the crates.io registry.
 	let crates_io = SourceId::crates_io(config).expect("Unable to create crates.io source ID");
 	let yanked_whitelist = HashSet::new();
-	let mut source = RegistrySource::remote(crates_io, &yanked_whitelist, config)
+	// Local registries replace crates.io through the config
+	let mut source = SourceConfigMap::new(config)
+		.and_then(|sources| sources.load(crates_io, &yanked_whitelist))
 		.expect("Unable to create registry source");
 	source.invalidate_cache();
 	source.block_until_ready().expect("Unable to wait for registry to be ready");
 
-	GlobalState { config, target_info, registry, crates_io, source, modifications }
+	GlobalState {
+		config,
+		target_info,
+		wasm_target_info,
+		registry,
+		crates_io,
+		source,
+		modifications,
+	}
+}
+
+fn make_target_info(config: &Config, platform: &str) -> TargetInfo {
+	let compile_target = CompileTarget::new(platform).expect("Unable to create a CompileTarget");
+	let compile_kind = CompileKind::Target(compile_target);
+	let rustc = config.load_global_rustc(None).expect("Unable to load the global rustc");
+	TargetInfo::new(config, &[compile_kind], &rustc, compile_kind)
+		.expect("Unable to create a TargetInfo")
 }
 
 fn bulk_download(global: &mut GlobalState<'_>, package_ids: &[PackageId]) -> Vec<Package> {
@@ -250,44 +372,89 @@ fn bulk_download(global: &mut GlobalState<'_>, package_ids: &[PackageId]) -> Vec
 		.collect()
 }
 
-fn populate_initial_direct_dependencies(
-	global: &mut GlobalState<'_>,
-) -> BTreeMap<PackageId, ResolvedDep> {
-	let mut top = TopCrates::download();
-	top.add_rust_cookbook_crates();
-	top.add_curated_crates(global.modifications);
-
-	// Find the newest (non-prerelease, non-yanked) versions of all
-	// the interesting crates.
-	let mut package_ids = Vec::new();
-	for Crate { name } in top.crates {
-		if global.modifications.excluded(&name) {
-			continue;
-		}
+/// Finds the newest (non-prerelease, non-yanked) version of a crate that
+/// matches its pinned version, if it has one.
+fn newest_version(global: &mut GlobalState<'_>, name: InternedString) -> Option<Summary> {
+	// Query the registry for a summary of this crate.
+	// Usefully, this doesn't seem to include yanked versions
+	let modification = global.modifications.crate_modification(&name);
+	let version = modification.and_then(|m| m.version.as_deref());
+	let dep = Dependency::parse(name, version, global.crates_io)
+		.unwrap_or_else(|e| panic!("Unable to parse dependency for {}: {}", name, e));
+
+	let matches = match global.source.query_vec(&dep, QueryKind::Exact) {
+		Poll::Ready(Ok(v)) => v,
+		Poll::Ready(Err(e)) => panic!("Unable to query registry for {}: {}", name, e),
+		Poll::Pending => panic!("Registry not ready to query"),
+	};
+
+	// Find the newest non-prelease version, unless a prerelease was asked for
+	matches
+		.into_iter()
+		.filter(|summary| version.is_some() || !summary.version().is_prerelease())
+		.max_by_key(|summary| summary.version().clone())
+}
 
-		// Query the registry for a summary of this crate.
-		// Usefully, this doesn't seem to include yanked versions
-		let version = None;
-		let dep = Dependency::parse(name, version, global.crates_io)
-			.unwrap_or_else(|e| panic!("Unable to parse dependency for {}: {}", name, e));
+fn initial_summaries(global: &mut GlobalState<'_>, crate_list: &CrateList) -> Vec<Summary> {
+	let mut summaries = Vec::new();
 
-		let matches = match global.source.query_vec(&dep, QueryKind::Exact) {
-			Poll::Ready(Ok(v)) => v,
-			Poll::Ready(Err(e)) => panic!("Unable to query registry for {}: {}", name, e),
-			Poll::Pending => panic!("Registry not ready to query"),
-		};
+	match crate_list {
+		CrateList::Popular | CrateList::File(_) => {
+			let mut top = match crate_list {
+				CrateList::File(path) => TopCrates::read(path),
+				_ => {
+					let mut top = TopCrates::download();
+					top.add_rust_cookbook_crates();
+					top
+				}
+			};
+			top.add_curated_crates(global.modifications);
 
-		// Find the newest non-prelease version
-		let summary = matches
-			.into_iter()
-			.filter(|summary| !summary.version().is_prerelease())
-			.max_by_key(|summary| summary.version().clone())
-			.unwrap_or_else(|| panic!("Registry has no viable versions of {}", name));
+			for Crate { name } in top.crates {
+				if global.modifications.excluded(&name) {
+					continue;
+				}
 
-		let package_id = PackageId::pure(name, summary.version().clone(), global.crates_io);
-		package_ids.push(package_id);
+				let summary = newest_version(global, name)
+					.unwrap_or_else(|| panic!("Registry has no viable versions of {}", name));
+				summaries.push(summary);
+			}
+		}
+		CrateList::Deck(names) => {
+			let required = global.modifications.required.iter().map(|name| name.to_string());
+			let names: BTreeSet<_> = names.iter().cloned().chain(required).collect();
+
+			for name in names {
+				// Code uses the name of a crate's library, which has
+				// underscores where the crate's name has hyphens
+				let mut spellings = vec![name.clone(), name.replace('_', "-")];
+				spellings.dedup();
+				spellings.retain(|spelling| !global.modifications.excluded(spelling));
+
+				let summary = spellings
+					.into_iter()
+					.find_map(|spelling| newest_version(global, InternedString::new(&spelling)));
+
+				match summary {
+					Some(summary) => summaries.push(summary),
+					None => eprintln!("Skipping {}, which has no viable versions", name),
+				}
+			}
+		}
 	}
 
+	summaries
+}
+
+fn populate_initial_direct_dependencies(
+	global: &mut GlobalState<'_>,
+	crate_list: &CrateList,
+) -> BTreeMap<PackageId, ResolvedDep> {
+	let package_ids = initial_summaries(global, crate_list)
+		.into_iter()
+		.map(|summary| PackageId::pure(summary.name(), summary.version().clone(), global.crates_io))
+		.collect_vec();
+
 	let packages = bulk_download(global, &package_ids);
 
 	let mut initial_direct_dependencies = BTreeMap::new();
@@ -300,11 +467,18 @@ fn populate_initial_direct_dependencies(
 			lib_target,
 			features: BTreeSet::new(),
 			uses_default_features: true,
+			builds_for_wasm: true,
 		};
 		if let Some((features, default_features)) = playground_metadata_features(&download) {
 			dep.features = features;
 			dep.uses_default_features = default_features;
 		}
+		if let Some(modification) = global.modifications.crate_modification(&id.name()) {
+			dep.features.extend(modification.features(download.summary()));
+			if let Some(default_features) = modification.default_features {
+				dep.uses_default_features = default_features;
+			}
+		}
 		initial_direct_dependencies.insert(id, dep);
 	}
 
@@ -317,9 +491,9 @@ fn extend_direct_dependencies(
 ) {
 	// Add a direct dependency on each starting crate.
 	let mut summaries = Vec::new();
-	let mut valid_for_our_platform = BTreeSet::new();
+	let mut roots = BTreeSet::new();
 	for dep in mem::take(crates).into_values() {
-		valid_for_our_platform.insert(dep.summary.package_id());
+		roots.insert(dep.summary.package_id());
 		summaries.push((
 			dep.summary,
 			ResolveOpts {
@@ -347,28 +521,17 @@ fn extend_direct_dependencies(
 	)
 	.expect("Unable to resolve dependencies");
 
-	// Find transitive deps compatible with the playground's platform.
-	let mut to_visit = valid_for_our_platform.clone();
-	while !to_visit.is_empty() {
-		let mut visit_next = BTreeSet::new();
-
-		for package_id in to_visit {
-			for (dep_pkg, deps) in resolve.deps(package_id) {
-				let for_this_platform = deps.iter().any(|dep| {
-					dep.platform().map_or(true, |platform| {
-						platform.matches(PLAYGROUND_TARGET_PLATFORM, global.target_info.cfg())
-					})
-				});
-
-				if for_this_platform {
-					valid_for_our_platform.insert(dep_pkg);
-					visit_next.insert(dep_pkg);
-				}
-			}
-		}
-
-		to_visit = visit_next;
-	}
+	// Find transitive deps compatible with the playground's platforms. Slides
+	// are built for wasm, but build scripts and proc macros run on the host.
+	let mut valid_for_our_platform =
+		reachable_for_platform(&resolve, &roots, PLAYGROUND_TARGET_PLATFORM, &global.target_info);
+	valid_for_our_platform.extend(reachable_for_platform(
+		&resolve,
+		&roots,
+		WASM_TARGET_PLATFORM,
+		&global.wasm_target_info,
+	));
+	let wasm_incompatible = wasm_incompatible_packages(global, &resolve);
 
 	// Remove invalid and excluded packages that have been added due to resolution
 	let package_ids = resolve
@@ -390,23 +553,120 @@ fn extend_direct_dependencies(
 			// If enabled, all default features are already included in
 			// `features` by the resolver.
 			uses_default_features: false,
+			builds_for_wasm: !wasm_incompatible.contains(&id),
 		};
 		if let Some((features, _default_features)) = playground_metadata_features(&download) {
 			dep.features.extend(features);
 		}
+		if let Some(modification) = global.modifications.crate_modification(&id.name()) {
+			dep.features.extend(modification.features(download.summary()));
+		}
 		crates.insert(id, dep);
 	}
 }
 
+fn for_platform(dep: &Dependency, platform: &str, target_info: &TargetInfo) -> bool {
+	dep.platform().map_or(true, |p| p.matches(platform, target_info.cfg()))
+}
+
+fn reachable_for_platform(
+	resolve: &Resolve,
+	roots: &BTreeSet<PackageId>,
+	platform: &str,
+	target_info: &TargetInfo,
+) -> BTreeSet<PackageId> {
+	let mut reachable = roots.clone();
+	let mut to_visit = roots.clone();
+	while !to_visit.is_empty() {
+		let mut visit_next = BTreeSet::new();
+
+		for package_id in to_visit {
+			for (dep_pkg, deps) in resolve.deps(package_id) {
+				let for_this_platform =
+					deps.iter().any(|dep| for_platform(dep, platform, target_info));
+
+				if for_this_platform && reachable.insert(dep_pkg) {
+					visit_next.insert(dep_pkg);
+				}
+			}
+		}
+
+		to_visit = visit_next;
+	}
+
+	reachable
+}
+
+/// Finds the packages that can't be built for wasm: the hand-picked ones,
+/// and any package that depends on one of those when built for wasm. Build
+/// dependencies don't count, since they run on the host.
+fn wasm_incompatible_packages(global: &GlobalState<'_>, resolve: &Resolve) -> HashSet<PackageId> {
+	let mut incompatible: HashSet<_> = resolve
+		.iter()
+		.filter(|pkg| global.modifications.wasm_incompatible(pkg.name().as_str()))
+		.collect();
+
+	loop {
+		let newly_incompatible = resolve
+			.iter()
+			.filter(|pkg| !incompatible.contains(pkg))
+			.filter(|&pkg| {
+				resolve.deps(pkg).any(|(dep_pkg, deps)| {
+					incompatible.contains(&dep_pkg)
+						&& deps.iter().any(|dep| {
+							dep.kind() != DepKind::Build
+								&& for_platform(dep, WASM_TARGET_PLATFORM, &global.wasm_target_info)
+						})
+				})
+			})
+			.collect_vec();
+
+		if newly_incompatible.is_empty() {
+			break;
+		}
+		incompatible.extend(newly_incompatible);
+	}
+
+	incompatible
+}
+
+/// Sets up cargo to use `registry` in place of crates.io, without going
+/// online.
+fn make_config(registry: &Registry) -> Config {
+	let mut config = Config::default().expect("Unable to create default Cargo config");
+
+	let (kind, path) = match registry {
+		Registry::CratesIo => return config,
+		Registry::Local(path) => ("local-registry", path),
+		Registry::Vendored(path) => ("directory", path),
+	};
+	let path =
+		path.canonicalize().unwrap_or_else(|e| panic!("Unable to find {}: {}", path.display(), e));
+	let path = toml::Value::String(path.to_string_lossy().into_owned());
+
+	let cli_config = [
+		"source.crates-io.replace-with = 'playground-local'".to_owned(),
+		format!("source.playground-local.{} = {}", kind, path),
+	];
+	let offline = true;
+	config
+		.configure(0, false, None, false, false, offline, &None, &[], &cli_config)
+		.expect("Unable to configure Cargo to use the local registry");
+
+	config
+}
+
 pub fn generate_info(
 	modifications: &Modifications,
+	crate_list: &CrateList,
+	registry: &Registry,
 ) -> (BTreeMap<String, DependencySpec>, Vec<CrateInformation>) {
 	// Setup to interact with cargo.
-	let config = Config::default().expect("Unable to create default Cargo config");
+	let config = make_config(registry);
 	let _lock = config.acquire_package_cache_lock(CacheLockMode::DownloadExclusive);
 	let mut global = make_global_state(&config, modifications);
 
-	let mut resolved_crates = populate_initial_direct_dependencies(&mut global);
+	let mut resolved_crates = populate_initial_direct_dependencies(&mut global, crate_list);
 
 	loop {
 		let num_crates_before = resolved_crates.len();
@@ -416,8 +676,12 @@ pub fn generate_info(
 		}
 	}
 
-	let dependencies = generate_dependency_specs(&resolved_crates);
+	let mut dependencies = generate_dependency_specs(&resolved_crates);
 	let infos = generate_crate_information(&dependencies);
+
+	// Every dependency is built along with each slide
+	dependencies.retain(|_, dependency_spec| dependency_spec.builds_for_wasm);
+
 	(dependencies, infos)
 }
 
@@ -466,6 +730,7 @@ fn generate_dependency_specs(
 					version: version.clone(),
 					features,
 					default_features,
+					builds_for_wasm: dep.builds_for_wasm,
 				},
 			);
 
@@ -486,8 +751,61 @@ fn generate_crate_information(
 			name: dependency_spec.package.clone(),
 			version: dependency_spec.version.clone(),
 			id: exposed_name.clone(),
+			wasm: dependency_spec.builds_for_wasm,
 		});
 	}
 
 	infos
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Adds a crate to a `cargo vendor` directory.
+	fn vendor_crate(directory: &Path, name: &str, version: &str, dependencies: &str) {
+		let crate_directory = directory.join(format!("{}-{}", name, version));
+		fs::create_dir_all(crate_directory.join("src")).unwrap();
+
+		let manifest = format!(
+			"[package]\nname = \"{}\"\nversion = \"{}\"\n\n[dependencies]\n{}\n",
+			name, version, dependencies
+		);
+		fs::write(crate_directory.join("Cargo.toml"), manifest).unwrap();
+		fs::write(crate_directory.join("src/lib.rs"), "").unwrap();
+		fs::write(crate_directory.join(".cargo-checksum.json"), r#"{"files":{}}"#).unwrap();
+	}
+
+	#[test]
+	fn crate_list_resolved_from_vendored_crates() {
+		let directory = std::env::temp_dir().join(format!("top-crates-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&directory);
+		let vendored = directory.join("vendor");
+
+		vendor_crate(&vendored, "alpha", "1.0.0", "beta = \"0.2\"");
+		vendor_crate(&vendored, "beta", "0.2.0", "");
+		vendor_crate(&vendored, "beta", "0.2.1", "");
+		vendor_crate(&vendored, "gamma", "0.1.0", "");
+		vendor_crate(&vendored, "unused", "1.0.0", "");
+
+		let crate_list = directory.join("crates.txt");
+		fs::write(&crate_list, "# The crates for the test\nalpha\n").unwrap();
+
+		let modifications = Modifications {
+			required: BTreeSet::from([InternedString::new("gamma")]),
+			..Modifications::default()
+		};
+
+		let (dependencies, infos) = generate_info(
+			&modifications,
+			&CrateList::File(crate_list),
+			&Registry::Vendored(vendored),
+		);
+		fs::remove_dir_all(&directory).unwrap();
+
+		let versions: Vec<_> =
+			dependencies.iter().map(|(name, spec)| format!("{} {}", name, spec.version)).collect();
+		assert_eq!(versions, ["alpha 1.0.0", "beta 0.2.1", "gamma 0.1.0"]);
+		assert!(infos.iter().all(|info| info.wasm));
+	}
+}
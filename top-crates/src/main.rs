@@ -58,7 +58,49 @@ fn main() {
 	let modifications: Modifications =
 		toml::from_str(&d).expect("unable to parse crate modifications file");
 
-	let (dependencies, infos) = rust_playground_top_crates::generate_info(&modifications);
+	// Arguments are the output directory, and options for choosing the
+	// crates and where to find them:
+	//
+	// --deck <path>            the crates for a deck, instead of the most
+	//                          popular crates; may be repeated
+	// --crate-list <path>      the crates named in a file, one per line
+	// --local-registry <path>  a local registry to use instead of crates.io
+	// --vendored <path>        a `cargo vendor` directory to use instead
+	let mut base_directory = None;
+	let mut deck_paths = Vec::new();
+	let mut crate_list_path = None;
+	let mut registry = Registry::CratesIo;
+	let mut args = std::env::args_os().skip(1);
+	while let Some(arg) = args.next() {
+		let mut path_for = |option: &str| {
+			PathBuf::from(args.next().unwrap_or_else(|| panic!("{} needs a path", option)))
+		};
+
+		match arg.to_str() {
+			Some("--deck") => deck_paths.push(path_for("--deck")),
+			Some("--crate-list") => crate_list_path = Some(path_for("--crate-list")),
+			Some("--local-registry") => registry = Registry::Local(path_for("--local-registry")),
+			Some("--vendored") => registry = Registry::Vendored(path_for("--vendored")),
+			_ => base_directory = Some(PathBuf::from(arg)),
+		}
+	}
+
+	let crate_list = match (crate_list_path, deck_paths.is_empty()) {
+		(Some(_), false) => panic!("--crate-list and --deck can't be used together"),
+		(Some(path), true) => CrateList::File(path),
+		(None, false) => CrateList::Deck(deck_crate_names(&deck_paths)),
+		(None, true) => CrateList::Popular,
+	};
+
+	// The most popular crates are only listed on crates.io
+	if let (CrateList::Popular, Registry::Local(_) | Registry::Vendored(_)) =
+		(&crate_list, &registry)
+	{
+		panic!("--local-registry and --vendored need --crate-list or --deck");
+	}
+
+	let (dependencies, infos) =
+		rust_playground_top_crates::generate_info(&modifications, &crate_list, &registry);
 
 	// Construct playground's Cargo.toml.
 	let manifest = TomlManifest {
@@ -85,10 +127,8 @@ fn main() {
 	};
 
 	// Write manifest file.
-	let base_directory: PathBuf = std::env::args_os()
-		.nth(1)
-		.unwrap_or_else(|| "../asa-server/compiler/rust-base".into())
-		.into();
+	let base_directory =
+		base_directory.unwrap_or_else(|| "../asa-server/compiler/rust-base".into());
 
 	let cargo_toml = base_directory.join("Cargo.toml");
 	write_manifest(manifest, &cargo_toml);